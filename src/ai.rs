@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+use crate::metrics::BoardMetrics;
+use crate::{Direction, Game};
+
+/// Anything that can score a board position for the purposes of move selection.
+///
+/// Evaluators must be shareable between threads so that searches can be run in parallel when the
+/// `parallel` feature is enabled.
+pub trait Evaluator: Send + Sync {
+    /// Returns an estimate of how favourable `game` is. Higher is better.
+    fn evaluate(&self, game: &Game) -> f64;
+
+    /// Whether all 8 rotations/reflections of a board are guaranteed to evaluate the same. Searches
+    /// only share cached results between symmetric positions if this is true, so evaluators must
+    /// opt in once they have been checked to be symmetric.
+    fn is_symmetric(&self) -> bool {
+        false
+    }
+}
+
+/// Picks the move that maximizes the points scored by the slide plus the evaluation of the
+/// resulting afterstate. Returns `None` if no move is possible.
+pub fn best_move<E: Evaluator + ?Sized>(game: &Game, evaluator: &E) -> Option<Direction> {
+    best_afterstate(game, evaluator).map(|(direction, _, _)| direction)
+}
+
+/// Same as `best_move()` but also returns the chosen afterstate and the points scored reaching it.
+pub fn best_afterstate<E: Evaluator + ?Sized>(game: &Game, evaluator: &E) -> Option<(Direction, Game, u32)> {
+    let mut best: Option<(f64, Direction, Game, u32)> = None;
+
+    for &direction in game.topology().directions() {
+        if let Some((afterstate, reward)) = game.afterstate(direction) {
+            let value = reward as f64 + evaluator.evaluate(&afterstate);
+
+            if best.as_ref().is_none_or(|(best_value, _, _, _)| value > *best_value) {
+                best = Some((value, direction, afterstate, reward));
+            }
+        }
+    }
+
+    best.map(|(_, direction, afterstate, reward)| (direction, afterstate, reward))
+}
+
+/// Weights for a handcrafted evaluation function that combines the features in `metrics`. Weights
+/// are expressed in points so that evaluations can be compared with the score gained by a move.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HeuristicWeights {
+    pub empty_cells: f64,
+    pub monotonicity: f64,
+    pub smoothness: f64,
+    pub merge_opportunities: f64,
+    pub max_tile_in_corner: f64,
+    pub snake_adherence: f64,
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        HeuristicWeights {
+            empty_cells: 270.0,
+            monotonicity: 47.0,
+            smoothness: 10.0,
+            merge_opportunities: 700.0,
+            max_tile_in_corner: 500.0,
+            snake_adherence: 500.0,
+        }
+    }
+}
+
+impl HeuristicWeights {
+    pub const NUM_WEIGHTS: usize = 6;
+
+    /// Returns the weights in declaration order, for use by optimizers.
+    pub fn to_array(&self) -> [f64; HeuristicWeights::NUM_WEIGHTS] {
+        [
+            self.empty_cells,
+            self.monotonicity,
+            self.smoothness,
+            self.merge_opportunities,
+            self.max_tile_in_corner,
+            self.snake_adherence,
+        ]
+    }
+
+    /// Inverse of `to_array()`.
+    pub fn from_array(weights: [f64; HeuristicWeights::NUM_WEIGHTS]) -> Self {
+        HeuristicWeights {
+            empty_cells: weights[0],
+            monotonicity: weights[1],
+            smoothness: weights[2],
+            merge_opportunities: weights[3],
+            max_tile_in_corner: weights[4],
+            snake_adherence: weights[5],
+        }
+    }
+
+    /// Reads weights from a JSON file such as the one written by the `tune_heuristic` tool.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).expect("Weights always serialize.");
+        std::fs::write(path, contents)
+    }
+
+    /// Weights that strongly favour keeping tiles ordered along a snake from a corner.
+    pub fn snake() -> Self {
+        HeuristicWeights {
+            empty_cells: 100.0,
+            monotonicity: 0.0,
+            smoothness: 0.0,
+            merge_opportunities: 100.0,
+            max_tile_in_corner: 1000.0,
+            snake_adherence: 5000.0,
+        }
+    }
+}
+
+impl Evaluator for HeuristicWeights {
+    fn evaluate(&self, game: &Game) -> f64 {
+        let metrics = BoardMetrics::from_game(game);
+
+        self.empty_cells * metrics.empty_cells as f64
+            + self.monotonicity * (metrics.row_monotonicity + metrics.col_monotonicity) as f64
+            + self.smoothness * metrics.smoothness as f64
+            + self.merge_opportunities * metrics.merge_opportunities as f64
+            + self.max_tile_in_corner * metrics.max_tile_in_corner as u8 as f64
+            + self.snake_adherence * metrics.snake_adherence
+    }
+
+    /// Every metric is measured over all corners and orientations of the board.
+    fn is_symmetric(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,90 @@
+//! Web Worker that runs AI searches off the main thread, so that deep searches never stall the
+//! frontend's animations.
+//!
+//! The frontend sends the board as an `ExponentGrid` and receives the recommended move
+//! asynchronously. Every search is tagged with an id chosen by the sender, which is echoed back in
+//! the response so that answers to requests the player has since made stale can be ignored.
+
+use gloo::timers::callback::Timeout;
+use gloo::worker::{HandlerId, Worker, WorkerScope};
+use serde::{Deserialize, Serialize};
+
+use crate::strategy::{ExpectimaxStrategy, Strategy};
+use crate::{Direction, ExponentGrid, Game};
+
+/// Script produced by trunk for the `ai_worker` binary, relative to the page.
+pub const AI_WORKER_PATH: &str = "./ai_worker.js";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum AiRequest {
+    /// Find the best move for `board` with an expectimax search `depth` moves deep. Replaces any
+    /// search that has not started yet.
+    Search { id: u32, board: ExponentGrid, depth: usize },
+    /// Drop the search that has not started yet, if any.
+    Cancel,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AiResponse {
+    /// Id of the `AiRequest::Search` being answered.
+    pub id: u32,
+    /// `None` if no move is possible.
+    pub direction: Option<Direction>,
+}
+
+pub enum AiWorkerMessage {
+    /// Run the pending search, if it is still wanted.
+    Search,
+}
+
+pub struct AiWorker {
+    strategy: ExpectimaxStrategy,
+    depth: usize,
+    pending: Option<(HandlerId, u32, ExponentGrid)>,
+}
+
+impl Worker for AiWorker {
+    type Message = AiWorkerMessage;
+    type Input = AiRequest;
+    type Output = AiResponse;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        AiWorker {
+            strategy: ExpectimaxStrategy::new(1),
+            depth: 1,
+            pending: None,
+        }
+    }
+
+    fn update(&mut self, scope: &WorkerScope<Self>, msg: Self::Message) {
+        match msg {
+            AiWorkerMessage::Search => {
+                let Some((handler, id, board)) = self.pending.take() else {
+                    return
+                };
+
+                let direction = self.strategy.choose(&Game::from_exponents(&board));
+                scope.respond(handler, AiResponse { id, direction });
+            },
+        }
+    }
+
+    fn received(&mut self, scope: &WorkerScope<Self>, msg: Self::Input, handler: HandlerId) {
+        match msg {
+            AiRequest::Search { id, board, depth } => {
+                if depth != self.depth {
+                    self.strategy = ExpectimaxStrategy::new(depth);
+                    self.depth = depth;
+                }
+
+                self.pending = Some((handler, id, board));
+
+                // Searches block the worker, so requests sent in the meantime queue up. Deferring the
+                // search until those have been received means only the newest one is ever run.
+                let scope = scope.clone();
+                Timeout::new(0, move || scope.send_message(AiWorkerMessage::Search)).forget();
+            },
+            AiRequest::Cancel => self.pending = None,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+//! Entry point of the Web Worker hosting the AI. Built by trunk alongside the frontend (see
+//! `index.html`); it does nothing useful when run natively.
+
+use gloo::worker::Registrable;
+use rust_2048::ai_worker::AiWorker;
+
+fn main() {
+    AiWorker::registrar().register();
+}
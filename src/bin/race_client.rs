@@ -0,0 +1,112 @@
+//! Plays a race on a race server with bots, one connection each, and prints the result.
+//!
+//! Usage: cargo run --release --features server --bin race_client -- [--address HOST:PORT]
+//!        [--bots N] [--strategy NAME] [--local]
+//!
+//! `--local` starts a server for the bots on a free local port instead of connecting to
+//! `--address`, so a whole match can be played without anything else running.
+
+use rust_2048::race_server::{self, RaceResult, ServerConfig};
+use rust_2048::strategy::{self, STRATEGY_NAMES};
+use std::net::TcpListener;
+use std::process::exit;
+use std::thread;
+
+struct Options {
+    address: String,
+    bots: usize,
+    strategy: String,
+    local: bool,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut address = String::from("127.0.0.1:9001");
+    let mut bots = 2;
+    let mut strategy = String::from("greedy");
+    let mut local = false;
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--address" => address = value()?,
+            "--bots" => bots = value()?.parse().map_err(|_| "Invalid bot count")?,
+            "--strategy" => strategy = value()?,
+            "--local" => local = true,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if bots == 0 {
+        return Err(String::from("A race needs at least one bot"))
+    }
+
+    if strategy::by_name(&strategy).is_none() {
+        return Err(format!("Unknown strategy {}. Expected one of: {}", strategy, STRATEGY_NAMES.join(", ")))
+    }
+
+    Ok(Options { address, bots, strategy, local })
+}
+
+/// Starts a server for `players` players on a free local port and returns its address.
+fn start_local_server(players: usize) -> std::io::Result<String> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?.to_string();
+
+    thread::spawn(move || race_server::serve(listener, ServerConfig { players, seed: None }));
+    Ok(address)
+}
+
+fn main() {
+    let mut options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+
+    if options.local {
+        options.address = start_local_server(options.bots).unwrap_or_else(|error| {
+            eprintln!("Failed to start a local server: {}", error);
+            exit(1);
+        });
+    }
+
+    let bots: Vec<_> = (0..options.bots)
+        .map(|bot| {
+            let address = options.address.clone();
+            let strategy_name = options.strategy.clone();
+
+            thread::spawn(move || {
+                let mut strategy = strategy::by_name(&strategy_name).unwrap();
+                race_server::play(&address, &format!("{}-{}", strategy_name, bot + 1), strategy.as_mut())
+            })
+        })
+        .collect();
+
+    let mut result: Option<RaceResult> = None;
+
+    for bot in bots {
+        match bot.join().expect("Bot thread panicked.") {
+            Ok(bot_result) => result = Some(bot_result),
+            Err(error) => {
+                eprintln!("Bot failed: {}", error);
+                exit(1);
+            },
+        }
+    }
+
+    let Some(result) = result else {
+        return
+    };
+
+    for (place, standing) in result.standings.iter().enumerate() {
+        println!("{}. {:<14} score {:>7}  max tile {:>6}  moves {:>5}",
+                 place + 1, standing.name, standing.score, standing.max_tile, standing.moves);
+    }
+
+    match result.winner {
+        Some(winner) => println!("{} wins.", result.standings.iter().find(|standing| standing.player == winner).unwrap().name),
+        None => println!("Tie."),
+    }
+}
@@ -0,0 +1,60 @@
+//! Hosts multiplayer races over WebSocket. See `rust_2048::race_server` for the protocol.
+//!
+//! Usage: cargo run --release --features server --bin race_server -- [--address HOST:PORT]
+//!        [--players N] [--seed S]
+//!
+//! Every race starts once `--players` players have joined. `--seed` fixes the seed of the first race;
+//! later races count up from it.
+
+use rust_2048::race_server::{self, ServerConfig};
+use std::net::TcpListener;
+use std::process::exit;
+
+struct Options {
+    address: String,
+    config: ServerConfig,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut address = String::from("127.0.0.1:9001");
+    let mut players = 2;
+    let mut seed = None;
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--address" => address = value()?,
+            "--players" => players = value()?.parse().map_err(|_| "Invalid player count")?,
+            "--seed" => seed = Some(value()?.parse().map_err(|_| "Invalid seed")?),
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if players == 0 {
+        return Err(String::from("A race needs at least one player"))
+    }
+
+    Ok(Options { address, config: ServerConfig { players, seed } })
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+
+    let listener = TcpListener::bind(&options.address).unwrap_or_else(|error| {
+        eprintln!("Failed to listen on {}: {}", options.address, error);
+        exit(1);
+    });
+
+    println!("Hosting races for {} players on ws://{}/", options.config.players, options.address);
+
+    if let Err(error) = race_server::serve(listener, options.config) {
+        eprintln!("Server stopped: {}", error);
+        exit(1);
+    }
+}
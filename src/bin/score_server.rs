@@ -0,0 +1,53 @@
+//! Serves replay-verified high scores over HTTP. See `rust_2048::score_server` for the endpoints.
+//!
+//! Usage: cargo run --release --features server --bin score_server -- [--address HOST:PORT]
+//!        [--data DIRECTORY]
+//!
+//! Scores and replays are kept in `--data`, `scores` by default.
+
+use rust_2048::score_server::{self, ScoreStore};
+use std::process::exit;
+
+struct Options {
+    address: String,
+    data: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut address = String::from("127.0.0.1:9002");
+    let mut data = String::from("scores");
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--address" => address = value()?,
+            "--data" => data = value()?,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(Options { address, data })
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+
+    let store = ScoreStore::open(&options.data).unwrap_or_else(|error| {
+        eprintln!("Failed to open the score store in {}: {}", options.data, error);
+        exit(1);
+    });
+
+    let server = tiny_http::Server::http(&options.address).unwrap_or_else(|error| {
+        eprintln!("Failed to listen on {}: {}", options.address, error);
+        exit(1);
+    });
+
+    println!("Serving scores from {} on http://{}/", options.data, options.address);
+    score_server::serve(server, store);
+}
@@ -0,0 +1,137 @@
+//! Pits strategies against each other on a shared list of seeds and writes CSV and JSON reports.
+//!
+//! Usage: cargo run --release --bin tournament -- [--strategies random,greedy,...] [--games N]
+//!        [--first-seed S] [--seeds 1,2,3] [--goals 2048,4096] [--max-moves N] [--output PREFIX]
+//!        [--weights FILE]
+//!
+//! `--weights` loads heuristic weights (e.g. from `tune_heuristic`) for the expectimax strategy.
+//!
+//! Writes `PREFIX_games.csv`, `PREFIX_summary.csv` and `PREFIX.json`.
+
+use rust_2048::ai::HeuristicWeights;
+use rust_2048::strategy::{self, ExpectimaxStrategy, Strategy, STRATEGY_NAMES};
+use rust_2048::tournament::{self, StrategyFactory};
+use std::process::exit;
+
+struct Options {
+    strategies: Vec<String>,
+    seeds: Vec<u64>,
+    goals: Vec<u32>,
+    max_moves: Option<usize>,
+    output: String,
+    weights: Option<String>,
+}
+
+fn parse_list<T: std::str::FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value.split(',')
+        .map(|item| item.trim().parse().map_err(|_| format!("Invalid list item: {}", item)))
+        .collect()
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut strategies: Vec<String> = STRATEGY_NAMES.iter().map(|name| name.to_string()).collect();
+    let mut seeds = None;
+    let mut games = 10;
+    let mut first_seed = 1;
+    let mut goals = vec![2048, 4096];
+    let mut max_moves = None;
+    let mut output = String::from("tournament");
+    let mut weights = None;
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--strategies" => strategies = value()?.split(',').map(|name| name.trim().to_string()).collect(),
+            "--seeds" => seeds = Some(parse_list(&value()?)?),
+            "--games" => games = value()?.parse().map_err(|_| "Invalid game count")?,
+            "--first-seed" => first_seed = value()?.parse().map_err(|_| "Invalid seed")?,
+            "--goals" => goals = parse_list(&value()?)?,
+            "--max-moves" => max_moves = Some(value()?.parse().map_err(|_| "Invalid move limit")?),
+            "--output" => output = value()?,
+            "--weights" => weights = Some(value()?),
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    let seeds = seeds.unwrap_or_else(|| (first_seed..first_seed + games).collect());
+
+    Ok(Options {
+        strategies,
+        seeds,
+        goals,
+        max_moves,
+        output,
+        weights,
+    })
+}
+
+fn write_file(path: &str, contents: &str) {
+    if let Err(error) = std::fs::write(path, contents) {
+        eprintln!("Failed to write {}: {}", path, error);
+        exit(1);
+    }
+
+    println!("Wrote {}", path);
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+
+    let weights = options.weights.as_ref().map(|path| {
+        HeuristicWeights::load(path).unwrap_or_else(|error| {
+            eprintln!("Failed to load {}: {}", path, error);
+            exit(1);
+        })
+    });
+
+    let mut strategies: Vec<Box<dyn Fn() -> Box<dyn Strategy> + Sync>> = Vec::new();
+
+    for name in options.strategies {
+        if let (Some(weights), "expectimax") = (weights, name.as_str()) {
+            strategies.push(Box::new(move || Box::new(ExpectimaxStrategy::with_evaluator(2, Box::new(weights)))));
+            continue
+        }
+
+        if strategy::by_name(&name).is_none() {
+            eprintln!("Unknown strategy {}. Expected one of: {}", name, STRATEGY_NAMES.join(", "));
+            exit(1);
+        }
+
+        strategies.push(Box::new(move || strategy::by_name(&name).unwrap()));
+    }
+
+    let strategies: Vec<StrategyFactory> = strategies.iter().map(|make_strategy| make_strategy.as_ref()).collect();
+
+    let report = tournament::run(&strategies, &options.seeds, &options.goals, options.max_moves, |game| {
+        println!("{:<14} seed {:>6}  score {:>7}  max tile {:>6}  moves {:>5}",
+                 game.strategy, game.seed, game.score, game.max_tile, game.moves);
+    });
+
+    println!();
+
+    for summary in &report.summaries {
+        let win_rates: Vec<String> = summary.win_rates.iter()
+            .map(|win_rate| format!("{}: {:.1}%", win_rate.goal, 100.0 * win_rate.rate))
+            .collect();
+
+        println!("{:<14} mean {:>9.1}  median {:>9.1}  best {:>7}  {:>8.1}µs/move  {}",
+                 summary.strategy,
+                 summary.mean_score,
+                 summary.median_score,
+                 summary.best_score,
+                 summary.micros_per_move,
+                 win_rates.join("  "));
+    }
+
+    println!();
+
+    write_file(&format!("{}_games.csv", options.output), &report.games_csv());
+    write_file(&format!("{}_summary.csv", options.output), &report.summary_csv());
+    write_file(&format!("{}.json", options.output), &report.to_json());
+}
@@ -0,0 +1,114 @@
+//! Trains an n-tuple network by self-play and saves its weights to a file.
+//!
+//! Usage: cargo run --release --bin train_ntuple -- [--episodes N] [--learning-rate A]
+//!        [--patterns four|six] [--no-symmetry] [--load FILE] [--output FILE] [--report N]
+
+use rust_2048::ntuple::NTupleNetwork;
+use std::process::exit;
+
+struct Options {
+    episodes: usize,
+    learning_rate: f32,
+    patterns: String,
+    symmetric: bool,
+    load: Option<String>,
+    output: String,
+    report_interval: usize,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        episodes: 10000,
+        learning_rate: 0.1,
+        patterns: String::from("four"),
+        symmetric: true,
+        load: None,
+        output: String::from("ntuple_weights.bin"),
+        report_interval: 100,
+    };
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--episodes" => options.episodes = value()?.parse().map_err(|_| "Invalid episode count")?,
+            "--learning-rate" => options.learning_rate = value()?.parse().map_err(|_| "Invalid learning rate")?,
+            "--patterns" => options.patterns = value()?,
+            "--no-symmetry" => options.symmetric = false,
+            "--load" => options.load = Some(value()?),
+            "--output" => options.output = value()?,
+            "--report" => options.report_interval = value()?.parse().map_err(|_| "Invalid report interval")?,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    options.report_interval = options.report_interval.max(1);
+
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+
+    let mut network = match &options.load {
+        Some(path) => NTupleNetwork::load(path).unwrap_or_else(|error| {
+            eprintln!("Failed to load {}: {}", path, error);
+            exit(1);
+        }),
+        None => {
+            let patterns = match options.patterns.as_str() {
+                "four" => NTupleNetwork::four_tuple_patterns(),
+                "six" => NTupleNetwork::six_tuple_patterns(),
+                other => {
+                    eprintln!("Unknown pattern set: {}", other);
+                    exit(1);
+                }
+            };
+
+            NTupleNetwork::new(patterns, options.symmetric)
+        },
+    };
+
+    let mut total_score = 0u64;
+    let mut best_score = 0;
+    let mut reached_2048 = 0;
+
+    for episode in 1..=options.episodes {
+        let result = network.train_episode(options.learning_rate);
+
+        total_score += result.score as u64;
+        best_score = best_score.max(result.score);
+
+        if result.max_tile >= 2048 {
+            reached_2048 += 1;
+        }
+
+        if episode % options.report_interval == 0 {
+            println!("episode {:>8}  mean score {:>8.0}  best {:>7}  2048 rate {:>5.1}%",
+                     episode,
+                     total_score as f64 / options.report_interval as f64,
+                     best_score,
+                     100.0 * reached_2048 as f64 / options.report_interval as f64);
+
+            total_score = 0;
+            best_score = 0;
+            reached_2048 = 0;
+
+            if let Err(error) = network.save(&options.output) {
+                eprintln!("Failed to save {}: {}", options.output, error);
+            }
+        }
+    }
+
+    if let Err(error) = network.save(&options.output) {
+        eprintln!("Failed to save {}: {}", options.output, error);
+        exit(1);
+    }
+
+    println!("Saved weights to {}", options.output);
+}
@@ -0,0 +1,86 @@
+//! Tunes the weights of the handcrafted evaluation function with a genetic algorithm.
+//!
+//! Usage: cargo run --release --bin tune_heuristic -- [--generations N] [--population N]
+//!        [--elites N] [--games N] [--mutation X] [--max-moves N] [--seed S]
+//!        [--checkpoint FILE] [--output FILE]
+//!
+//! If the checkpoint file exists the run resumes from it, ignoring the other tuning options. The
+//! best weights are written to the output file, which `HeuristicWeights::load()` can read.
+
+use rust_2048::tuner::{Checkpoint, Tuner, TunerConfig};
+use std::path::Path;
+use std::process::exit;
+
+struct Options {
+    config: TunerConfig,
+    generations: usize,
+    checkpoint: String,
+    output: String,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        config: TunerConfig::default(),
+        generations: 20,
+        checkpoint: String::from("tuner_checkpoint.json"),
+        output: String::from("heuristic_weights.json"),
+    };
+
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+
+        match arg.as_str() {
+            "--generations" => options.generations = value()?.parse().map_err(|_| "Invalid generation count")?,
+            "--population" => options.config.population = value()?.parse().map_err(|_| "Invalid population size")?,
+            "--elites" => options.config.elites = value()?.parse().map_err(|_| "Invalid elite count")?,
+            "--games" => options.config.games_per_evaluation = value()?.parse().map_err(|_| "Invalid game count")?,
+            "--mutation" => options.config.mutation_strength = value()?.parse().map_err(|_| "Invalid mutation strength")?,
+            "--max-moves" => options.config.max_moves = Some(value()?.parse().map_err(|_| "Invalid move limit")?),
+            "--seed" => options.config.seed = value()?.parse().map_err(|_| "Invalid seed")?,
+            "--checkpoint" => options.checkpoint = value()?,
+            "--output" => options.output = value()?,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() {
+    let options = parse_options().unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1);
+    });
+
+    let mut tuner = if Path::new(&options.checkpoint).exists() {
+        let checkpoint = Checkpoint::load(&options.checkpoint).unwrap_or_else(|error| {
+            eprintln!("Failed to load {}: {}", options.checkpoint, error);
+            exit(1);
+        });
+
+        println!("Resuming from generation {} of {}", checkpoint.generation, options.checkpoint);
+        Tuner::from_checkpoint(checkpoint)
+    } else {
+        Tuner::new(options.config.clone())
+    };
+
+    while tuner.checkpoint().generation < options.generations {
+        let stats = tuner.step();
+
+        println!("generation {:>4}  best {:>9.1}  mean {:>9.1}  {:?}",
+                 stats.generation, stats.best_fitness, stats.mean_fitness, stats.best.to_array());
+
+        if let Err(error) = tuner.checkpoint().save(&options.checkpoint) {
+            eprintln!("Failed to save {}: {}", options.checkpoint, error);
+        }
+    }
+
+    if let Err(error) = tuner.best().save(&options.output) {
+        eprintln!("Failed to save {}: {}", options.output, error);
+        exit(1);
+    }
+
+    println!("Saved best weights (mean score {:.1}) to {}", tuner.checkpoint().best_fitness, options.output);
+}
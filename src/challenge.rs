@@ -0,0 +1,133 @@
+//! Short, human-shareable codes for game seeds.
+//!
+//! Games started from the same code see exactly the same spawn sequence, so players can compare
+//! their results on it. Codes use Crockford's base 32 alphabet, which leaves out letters that are
+//! easily confused with digits and accepts them as those digits when typed in.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BITS_PER_CHARACTER: u32 = 5;
+const GROUP_SIZE: usize = 4;
+
+/// Seeds of new challenges are kept to 40 bits, which makes for 8 character codes.
+pub const CHALLENGE_SEED_BITS: u32 = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeError {
+    Empty,
+    InvalidCharacter(char),
+    /// The code does not fit in a 64 bit seed.
+    TooLong,
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeError::Empty => write!(f, "the code is empty"),
+            CodeError::InvalidCharacter(c) => write!(f, "{:?} is not a valid character", c),
+            CodeError::TooLong => write!(f, "the code is too long"),
+        }
+    }
+}
+
+/// Result of one game played from a challenge code.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChallengeResult {
+    pub score: u32,
+    pub moves: usize,
+    pub max_tile: u32,
+}
+
+/// Returns a random seed for a new challenge.
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen::<u64>() >> (64 - CHALLENGE_SEED_BITS)
+}
+
+/// Seed of the daily challenge for a calendar date. Everyone playing on the same date gets the same
+/// game.
+pub fn daily_seed(year: i32, month: u32, day: u32) -> u64 {
+    let date = (year as i64 * 10_000 + month as i64 * 100 + day as i64) as u64;
+
+    // SplitMix64 finalizer, truncated so that daily codes are as short as other challenges.
+    let mut z = date.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (z ^ (z >> 31)) >> (64 - CHALLENGE_SEED_BITS)
+}
+
+/// Encodes a seed as a code of at least 8 characters, in groups of 4 separated by dashes.
+pub fn encode(seed: u64) -> String {
+    let mut digits = Vec::new();
+    let mut remaining = seed;
+
+    while remaining > 0 || digits.len() < (CHALLENGE_SEED_BITS / BITS_PER_CHARACTER) as usize {
+        digits.push(ALPHABET[(remaining % 32) as usize] as char);
+        remaining /= 32;
+    }
+
+    digits.reverse();
+
+    digits.chunks(GROUP_SIZE)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Decodes a code produced by `encode()`. Case, dashes and spaces are ignored, and the letters O, I
+/// and L are read as the digits they resemble.
+pub fn decode(code: &str) -> Result<u64, CodeError> {
+    let mut seed: u64 = 0;
+    let mut empty = true;
+
+    for c in code.chars().filter(|c| *c != '-' && !c.is_whitespace()) {
+        let c = match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        };
+
+        let value = ALPHABET.iter().position(|&a| a as char == c).ok_or(CodeError::InvalidCharacter(c))?;
+
+        seed = seed.checked_mul(32)
+            .and_then(|seed| seed.checked_add(value as u64))
+            .ok_or(CodeError::TooLong)?;
+        empty = false;
+    }
+
+    if empty {
+        return Err(CodeError::Empty)
+    }
+
+    Ok(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_round_trip() {
+        for seed in [0, 1, 31, 32, random_seed(), daily_seed(2024, 2, 29), u64::MAX] {
+            assert_eq!(decode(&encode(seed)), Ok(seed));
+        }
+
+        assert_eq!(encode(0), "0000-0000");
+        assert_eq!(encode(random_seed()).len(), 9);
+        assert_eq!(decode(&encode(u64::MAX).to_lowercase().replace('1', "l")), Ok(u64::MAX));
+
+        assert_eq!(decode(" - "), Err(CodeError::Empty));
+        assert_eq!(decode("AB!C"), Err(CodeError::InvalidCharacter('!')));
+        assert_eq!(decode("ZZZZ-ZZZZ-ZZZZ-Z"), Err(CodeError::TooLong));
+    }
+
+    #[test]
+    fn test_daily_seeds() {
+        assert_eq!(daily_seed(2024, 1, 1), daily_seed(2024, 1, 1));
+        assert_ne!(daily_seed(2024, 1, 1), daily_seed(2024, 1, 2));
+        assert_ne!(daily_seed(2024, 1, 1), daily_seed(2025, 1, 1));
+        assert!(daily_seed(2024, 1, 1) < 1 << CHALLENGE_SEED_BITS);
+    }
+}
@@ -0,0 +1,117 @@
+//! Ghosts: replays of earlier games played back against the clock, so that a new game on the same
+//! seed can race them move for move.
+//!
+//! The ghost's clock starts with the player's first move. Every move is made as long after that as
+//! it was after the first move of the recorded game, or at a steady pace for replays recorded
+//! without timing.
+
+use crate::replay::{Divergence, Replay};
+use crate::{Game, InputResult};
+
+/// Time between the moves of ghosts whose replays were recorded without timing, in milliseconds.
+pub const UNTIMED_MOVE_INTERVAL: u32 = 400;
+
+pub struct Ghost {
+    replay: Replay,
+    game: Game,
+    position: usize,
+}
+
+impl Ghost {
+    /// Sets up a ghost at the start of `replay`, which must re-simulate to the recorded game.
+    pub fn new(replay: Replay) -> Result<Ghost, Divergence> {
+        replay.verify()?;
+        let game = replay.play_back_to(0)?;
+
+        Ok(Ghost { replay, game, position: 0 })
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// The ghost's board after the moves made so far.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Number of moves made so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn finished(&self) -> bool {
+        self.position >= self.replay.moves.len()
+    }
+
+    /// When the move at `index` is due, in milliseconds after the first move.
+    pub fn move_time(&self, index: usize) -> u32 {
+        match self.replay.times.get(index) {
+            Some(&time) => time,
+            None => index as u32 * UNTIMED_MOVE_INTERVAL,
+        }
+    }
+
+    /// Whether the next move is due `elapsed` milliseconds after the first move.
+    pub fn move_due(&self, elapsed: u32) -> bool {
+        !self.finished() && self.move_time(self.position) <= elapsed
+    }
+
+    /// Makes the next recorded move, or returns `None` once every move has been made.
+    pub fn step(&mut self) -> Option<InputResult<'_>> {
+        let &direction = self.replay.moves.get(self.position)?;
+        self.position += 1;
+
+        Some(self.game.make_move(direction))
+    }
+
+    /// How far `score` is ahead of the ghost's score, negative if it is behind.
+    pub fn score_delta(&self, score: u32) -> i64 {
+        score as i64 - self.game.score as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    fn recording() -> Replay {
+        Replay::record(12, &[Direction::Left, Direction::Up, Direction::Right, Direction::Down, Direction::Left])
+    }
+
+    #[test]
+    fn test_ghost_keeps_recorded_time() {
+        let replay = recording();
+        let times: Vec<u32> = (0..replay.moves.len() as u32).map(|index| index * 250).collect();
+        let replay = Replay::from_json(&replay.with_times(times).to_json()).unwrap();
+        let mut ghost = Ghost::new(replay.clone()).unwrap();
+
+        assert!(ghost.move_due(0));
+        ghost.step();
+        assert!(!ghost.move_due(249));
+        assert!(ghost.move_due(250));
+
+        while ghost.move_due(u32::MAX) {
+            ghost.step();
+        }
+
+        assert!(ghost.finished());
+        assert!(ghost.step().is_none());
+        assert_eq!(ghost.game().score, replay.final_score);
+        assert_eq!(ghost.score_delta(replay.final_score + 8), 8);
+
+        // Untimed recordings move at a steady pace.
+        let untimed = Ghost::new(recording()).unwrap();
+        assert_eq!(untimed.move_time(3), 3 * UNTIMED_MOVE_INTERVAL);
+        assert_eq!(recording().with_times(vec![0]).times, Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_tampered_replays_are_rejected() {
+        let mut replay = recording();
+        replay.final_score += 4;
+
+        assert!(Ghost::new(replay).is_err());
+    }
+}
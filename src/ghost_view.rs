@@ -0,0 +1,224 @@
+use gloo::storage::{LocalStorage, Storage};
+use gloo::timers::callback::Interval;
+use rust_2048::ghost::Ghost;
+use rust_2048::replay::Replay;
+use rust_2048::InputResult;
+use std::collections::HashMap;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::AnimationEvent;
+use yew::prelude::*;
+
+use crate::*;
+
+// Local storage key of the best replay of every challenge played, keyed by challenge code.
+const BEST_REPLAYS_STORAGE_KEY: &str = "rust-2048-best-replays";
+
+// How often the ghost checks whether its next move is due, in milliseconds.
+const GHOST_TICK: u32 = 50;
+
+pub enum GhostCommand {
+    /// Draws the ghost's board once it has been rendered.
+    Show,
+    /// Sent after every move of the player, with their new score. The first one starts the ghost.
+    PlayerMoved(u32),
+    /// Sent by the ghost's timer.
+    Tick,
+    /// Sent when the game board is unmounted.
+    Close,
+}
+
+/// Plays the best recorded game of the session's challenge on a small board next to the player's,
+/// in step with the player's own clock.
+pub struct GhostClient {
+    command_tx: UnboundedSender<GhostCommand>,
+    best_score: u32,
+}
+
+impl GhostClient {
+    pub fn new(ghost: Ghost) -> Self {
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let best_score = ghost.replay().final_score;
+
+        spawn_local(process_ghost_commands(ghost, command_rx, command_tx.clone()));
+
+        GhostClient { command_tx, best_score }
+    }
+
+    /// Final score of the game the ghost replays.
+    pub fn best_score(&self) -> u32 {
+        self.best_score
+    }
+
+    pub fn show(&self) {
+        self.command_tx.send(GhostCommand::Show).unwrap();
+    }
+
+    pub fn player_moved(&self, score: u32) {
+        self.command_tx.send(GhostCommand::PlayerMoved(score)).unwrap();
+    }
+
+    pub fn close(&self) {
+        self.command_tx.send(GhostCommand::Close).unwrap();
+    }
+}
+
+async fn process_ghost_commands(mut ghost: Ghost, mut command_rx: UnboundedReceiver<GhostCommand>, command_tx: UnboundedSender<GhostCommand>) {
+    let (animationend_tx, mut animationend_rx) = counted_channel::CountedChannel::new();
+    let mut animationend_tx = Some(animationend_tx);
+    let mut listeners = None;
+
+    let mut player_score = 0;
+    let mut started_at: Option<f64> = None;
+    // The ghost stops once the timer is dropped.
+    let mut timer: Option<Interval> = None;
+
+    while let Some(command) = command_rx.recv().await {
+        match command {
+            GhostCommand::Show => {
+                let Some(animationend_tx) = animationend_tx.take() else {
+                    continue
+                };
+
+                // The ghost's animations are counted on its own board and kept from reaching the
+                // body, where they would be counted as the player's.
+                let board = ghost_board();
+                let animationend_callback = animationend_callback(animationend_tx);
+                let stop_callback = Closure::wrap(Box::new(|event: AnimationEvent| event.stop_propagation()) as Box<dyn FnMut(AnimationEvent)>);
+
+                for callback in [&animationend_callback, &stop_callback] {
+                    board.add_event_listener_with_callback("animationend", callback.as_ref().unchecked_ref()).unwrap();
+                    board.add_event_listener_with_callback("animationcancel", callback.as_ref().unchecked_ref()).unwrap();
+                }
+
+                render_board(&board, ghost.game());
+                listeners = Some((board, animationend_callback, stop_callback));
+            },
+            GhostCommand::PlayerMoved(score) => {
+                player_score = score;
+
+                if started_at.is_none() {
+                    started_at = Some(js_sys::Date::now());
+
+                    let command_tx = command_tx.clone();
+                    timer = Some(Interval::new(GHOST_TICK, move || {
+                        command_tx.send(GhostCommand::Tick).unwrap();
+                    }));
+                }
+            },
+            // Ticks that were already queued when the ghost finished are ignored.
+            GhostCommand::Tick => {
+                let (Some(started_at), Some((board, _, _))) = (started_at, &listeners) else {
+                    continue
+                };
+
+                // The board is gone if a new game was started in the meantime.
+                if !board.is_connected() {
+                    break
+                }
+
+                if ghost.move_due((js_sys::Date::now() - started_at) as u32) {
+                    if let Some(InputResult::Ok(new_tile_id, tiles, _)) = ghost.step() {
+                        animate_move(board, &tiles, new_tile_id, &mut animationend_rx).await;
+                    }
+                }
+
+                if ghost.finished() {
+                    timer = None;
+                }
+            },
+            GhostCommand::Close => break,
+        }
+
+        update_ghost_scores(&ghost, player_score, started_at.is_some());
+    }
+
+    drop(timer);
+
+    if let Some((board, animationend_callback, stop_callback)) = listeners {
+        for callback in [&animationend_callback, &stop_callback] {
+            board.remove_event_listener_with_callback("animationend", callback.as_ref().unchecked_ref()).unwrap();
+            board.remove_event_listener_with_callback("animationcancel", callback.as_ref().unchecked_ref()).unwrap();
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct GhostPanelProps {
+    pub best_score: u32,
+}
+
+/// The ghost's board, shrunk, with its score and how far ahead of it the player is.
+#[function_component(GhostPanel)]
+pub fn ghost_panel(props: &GhostPanelProps) -> Html {
+    let style_args = format!("--status_text: {}", COLORS.text_light);
+
+    html! {
+        <div class="ghost" style={style_args}>
+            <div class="ghost-board">
+                <div class="board-container ghost">
+                    <GameBoard/>
+                </div>
+            </div>
+            <div class="ghost-info">
+                <span>{ "Ghost of your best game" }</span>
+                <span class="ghost-score">{ format!("Ghost 0 of {}", props.best_score) }</span>
+                <span class="ghost-delta">{ "Starts with your first move" }</span>
+            </div>
+        </div>
+    }
+}
+
+/// A ghost of the best game played on the session's challenge, if the session game is a challenge
+/// that has been played before and has not been started yet.
+pub fn session_ghost() -> Option<Ghost> {
+    if SESSION_START.lock().unwrap().is_some() || !SESSION_MOVES.lock().unwrap().is_empty() {
+        return None
+    }
+
+    let mut replays: HashMap<String, Replay> = LocalStorage::get(BEST_REPLAYS_STORAGE_KEY).unwrap_or_default();
+    replays.remove(&session_challenge_key()).and_then(|replay| Ghost::new(replay).ok())
+}
+
+/// Keeps `replay` as the best game of a challenge code if it beats the one stored.
+pub fn record_best_replay(code: &str, replay: Replay) {
+    let mut replays: HashMap<String, Replay> = LocalStorage::get(BEST_REPLAYS_STORAGE_KEY).unwrap_or_default();
+
+    if replays.get(code).is_some_and(|best| best.final_score >= replay.final_score) {
+        return
+    }
+
+    replays.insert(code.to_string(), replay);
+
+    if LocalStorage::set(BEST_REPLAYS_STORAGE_KEY, replays).is_err() {
+        log!("Best replay could not be saved.");
+    }
+}
+
+// Helper functions
+
+fn ghost_board() -> Element {
+    let document = gloo::utils::document();
+    document.query_selector(".board-container.ghost").unwrap().unwrap()
+}
+
+fn update_ghost_scores(ghost: &Ghost, player_score: u32, started: bool) {
+    let document = gloo::utils::document();
+
+    let (Some(score), Some(delta)) = (document.query_selector(".ghost-score").unwrap(), document.query_selector(".ghost-delta").unwrap()) else {
+        return
+    };
+
+    score.set_text_content(Some(&format!("Ghost {} of {}", ghost.game().score, ghost.replay().final_score)));
+
+    if started {
+        let text = match ghost.score_delta(player_score) {
+            0 => String::from("Even"),
+            difference if difference > 0 => format!("+{} ahead", difference),
+            difference => format!("{} behind", difference),
+        };
+
+        delta.set_text_content(Some(&text));
+    }
+}
@@ -0,0 +1,95 @@
+use gloo::worker::{Spawnable, WorkerBridge};
+use rust_2048::ai_worker::{AiRequest, AiWorker, AI_WORKER_PATH};
+use rust_2048::{Direction, ExponentGrid};
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+/// Depth of the expectimax search run by the worker for hints.
+const HINT_DEPTH: usize = 3;
+
+/// Requests move recommendations from the AI worker and shows them on the hint button.
+///
+/// Only the most recent request is ever answered: once the board changes, the pending request is
+/// cancelled and any response to it that is already on its way is ignored.
+pub struct HintClient {
+    bridge: WorkerBridge<AiWorker>,
+    board: Cell<ExponentGrid>,
+    next_id: Cell<u32>,
+    // Id of the request awaiting a response, shared with the bridge's callback.
+    pending_id: Rc<Cell<Option<u32>>>,
+}
+
+impl HintClient {
+    pub fn new() -> Self {
+        let pending_id = Rc::new(Cell::new(None));
+        let pending_id_for_callback = pending_id.clone();
+
+        let bridge = AiWorker::spawner()
+            .callback(move |response| {
+                if pending_id_for_callback.get() == Some(response.id) {
+                    pending_id_for_callback.set(None);
+                    set_hint_text(&hint_text(response.direction));
+                }
+            })
+            .spawn(AI_WORKER_PATH);
+
+        HintClient {
+            bridge,
+            board: Cell::new(Default::default()),
+            next_id: Cell::new(0),
+            pending_id,
+        }
+    }
+
+    /// Called whenever the board changes. Any hint for the previous board is discarded.
+    pub fn set_board(&self, board: ExponentGrid) {
+        self.board.set(board);
+        self.cancel();
+    }
+
+    /// Discards the pending hint, if any. Called as soon as the player moves, since the board is
+    /// only available again once the move's animations have finished.
+    pub fn cancel(&self) {
+        if self.pending_id.take().is_some() {
+            self.bridge.send(AiRequest::Cancel);
+        }
+
+        set_hint_text("Hint");
+    }
+
+    /// Asks the worker for the best move on the current board.
+    pub fn request(&self) {
+        let id = self.next_id.get();
+        self.next_id.set(id.wrapping_add(1));
+        self.pending_id.set(Some(id));
+
+        self.bridge.send(AiRequest::Search { id, board: self.board.get(), depth: HINT_DEPTH });
+        set_hint_text("Thinking...");
+    }
+}
+
+// Helper functions
+
+fn hint_text(direction: Option<Direction>) -> String {
+    let arrow = match direction {
+        Some(Direction::Up) => "↑",
+        Some(Direction::Down) => "↓",
+        Some(Direction::Left) => "←",
+        Some(Direction::Right) => "→",
+        // The worker plays the board as a square one, so it never suggests the hex diagonals.
+        Some(Direction::UpRight | Direction::DownLeft) | None => "-",
+    };
+
+    format!("Hint: {}", arrow)
+}
+
+fn set_hint_text(text: &str) {
+    let document = gloo::utils::document();
+
+    // The button is not rendered yet when the first board is set.
+    if let Some(hint_button) = document.query_selector(".hint").unwrap() {
+        hint_button.dyn_ref::<HtmlElement>().unwrap().set_inner_text(text);
+    }
+}
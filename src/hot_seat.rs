@@ -0,0 +1,238 @@
+//! Rules of hot-seat games, in which one player slides the tiles and a second player decides where
+//! every new tile appears instead of the random spawn.
+//!
+//! The players take turns, starting with the slider. The slider wins by reaching the goal tile; the
+//! placer wins by leaving the slider without a move.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+use crate::notation::{Position, Turn};
+use crate::spawn::{NoSpawner, Spawn};
+use crate::{Direction, Game, Tile};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Player {
+    /// Slides the tiles.
+    Slider,
+    /// Places a 2 or a 4 after every slide.
+    Placer,
+}
+
+impl Player {
+    /// Player who acts on `turn`.
+    pub fn on(turn: Turn) -> Player {
+        match turn {
+            Turn::Slide => Player::Slider,
+            Turn::Spawn => Player::Placer,
+        }
+    }
+}
+
+/// An action of either player, as chosen on their behalf when their time runs out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Slide(Direction),
+    Place(Spawn),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HotSeatError {
+    /// The game has already been won by one of the players.
+    Finished(Player),
+    /// It is the other player's turn.
+    NotYourTurn(Player),
+    /// The slide does not move any tiles.
+    InvalidMove(Direction),
+    /// The slot is off the board or already holds a tile.
+    Occupied { row: usize, col: usize },
+    /// The value is not one a spawned tile can take.
+    Value(u32),
+}
+
+impl fmt::Display for HotSeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HotSeatError::Finished(winner) => write!(f, "the game is over, {:?} won", winner),
+            HotSeatError::NotYourTurn(player) => write!(f, "it is the {:?}'s turn", player),
+            HotSeatError::InvalidMove(direction) => write!(f, "sliding {:?} does not move any tiles", direction),
+            HotSeatError::Occupied { row, col } => write!(f, "slot {},{} is not free", row, col),
+            HotSeatError::Value(value) => write!(f, "{} is not a value a new tile can take", value),
+        }
+    }
+}
+
+impl std::error::Error for HotSeatError {}
+
+#[derive(Clone, PartialEq)]
+pub struct HotSeatGame {
+    game: Game,
+    turn: Turn,
+    winner: Option<Player>,
+}
+
+impl HotSeatGame {
+    /// Starts from the board of `game`, with the slider to move. Nothing spawns on its own from then
+    /// on.
+    pub fn new(mut game: Game) -> Self {
+        game.set_spawner(Box::new(NoSpawner));
+
+        let mut hot_seat = HotSeatGame { game, turn: Turn::Slide, winner: None };
+        hot_seat.check_winner();
+        hot_seat
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn turn(&self) -> Turn {
+        self.turn
+    }
+
+    /// Player whose turn it is.
+    pub fn to_act(&self) -> Player {
+        Player::on(self.turn)
+    }
+
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    /// The board, score and turn, in notation.
+    pub fn position(&self) -> Position {
+        Position { next: self.turn, ..Position::of(&self.game) }
+    }
+
+    /// Slides the tiles for the slider, returning the tiles as they were moved.
+    pub fn slide(&mut self, direction: Direction) -> Result<Vec<&Tile>, HotSeatError> {
+        self.check_turn(Player::Slider)?;
+
+        if self.game.afterstate(direction).is_none() {
+            return Err(HotSeatError::InvalidMove(direction))
+        }
+
+        self.game.make_move(direction);
+        self.turn = Turn::Spawn;
+        self.check_winner();
+
+        Ok(self.game.get_tiles())
+    }
+
+    /// Places a tile for the placer, returning its ID.
+    pub fn place(&mut self, row: usize, col: usize, value: u32) -> Result<usize, HotSeatError> {
+        self.check_turn(Player::Placer)?;
+
+        if !self.game.config().spawn_values.contains(&value) {
+            return Err(HotSeatError::Value(value))
+        }
+
+        let id = self.game.place_tile(row, col, value).ok_or(HotSeatError::Occupied { row, col })?;
+        self.turn = Turn::Slide;
+        self.check_winner();
+
+        Ok(id)
+    }
+
+    /// A random action for the player whose turn it is: a slide in a random direction that moves
+    /// tiles, or a tile spawned as in a normal game. Returns `None` once the game is over.
+    pub fn random_action<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Action> {
+        if self.winner.is_some() {
+            return None
+        }
+
+        match self.turn {
+            Turn::Slide => {
+                let directions: Vec<Direction> = Direction::ALL.into_iter()
+                    .filter(|&direction| self.game.afterstate(direction).is_some())
+                    .collect();
+
+                directions.choose(rng).map(|&direction| Action::Slide(direction))
+            },
+            Turn::Spawn => self.game.random_spawn(rng).map(Action::Place),
+        }
+    }
+
+    /// Plays `action` for whichever player it belongs to.
+    pub fn play(&mut self, action: Action) -> Result<(), HotSeatError> {
+        match action {
+            Action::Slide(direction) => self.slide(direction).map(|_| ()),
+            Action::Place(spawn) => self.place(spawn.row, spawn.col, spawn.value).map(|_| ()),
+        }
+    }
+
+    fn check_turn(&self, player: Player) -> Result<(), HotSeatError> {
+        if let Some(winner) = self.winner {
+            return Err(HotSeatError::Finished(winner))
+        }
+
+        match self.to_act() == player {
+            true => Ok(()),
+            false => Err(HotSeatError::NotYourTurn(self.to_act())),
+        }
+    }
+
+    /// The slider wins on reaching the goal tile, even if that leaves no move. Otherwise the placer
+    /// wins once the slider has no move left.
+    fn check_winner(&mut self) {
+        if self.game.max_tile() >= self.game.config().goal_tile {
+            self.winner = Some(Player::Slider);
+        } else if self.turn == Turn::Slide && self.game.game_over() {
+            self.winner = Some(Player::Placer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turns() {
+        let mut hot_seat = HotSeatGame::new(Game::from_notation("2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0").unwrap());
+
+        assert_eq!(hot_seat.place(3, 3, 2), Err(HotSeatError::NotYourTurn(Player::Slider)));
+        assert_eq!(hot_seat.slide(Direction::Up).err(), Some(HotSeatError::InvalidMove(Direction::Up)));
+        assert!(hot_seat.slide(Direction::Right).is_ok());
+        assert_eq!(hot_seat.position().to_string(), "0,0,0,4/0,0,0,0/0,0,0,0/0,0,0,0 score=4 next=spawn");
+
+        assert_eq!(hot_seat.slide(Direction::Left).err(), Some(HotSeatError::NotYourTurn(Player::Placer)));
+        assert_eq!(hot_seat.place(0, 3, 2), Err(HotSeatError::Occupied { row: 0, col: 3 }));
+        assert_eq!(hot_seat.place(0, 0, 8), Err(HotSeatError::Value(8)));
+        assert!(hot_seat.place(0, 0, 4).is_ok());
+        assert_eq!(hot_seat.turn(), Turn::Slide);
+        assert_eq!(hot_seat.game().get_tiles().len(), 2);
+    }
+
+    #[test]
+    fn test_winners() {
+        // Sliding right makes the goal tile.
+        let mut hot_seat = HotSeatGame::new(Game::from_notation("0,0,1024,1024/0,0,0,0/0,0,0,0/0,0,0,0").unwrap());
+        hot_seat.slide(Direction::Right).unwrap();
+        assert_eq!(hot_seat.winner(), Some(Player::Slider));
+        assert_eq!(hot_seat.place(0, 0, 2), Err(HotSeatError::Finished(Player::Slider)));
+        assert_eq!(hot_seat.random_action(&mut rand::thread_rng()), None);
+
+        // A 4 in the last free slot leaves the slider stuck.
+        let mut hot_seat = HotSeatGame::new(Game::from_notation("2,4,2,4/4,2,4,2/2,4,2,4/2,4,2,0").unwrap());
+        assert!(hot_seat.slide(Direction::Left).is_err());
+        hot_seat.slide(Direction::Right).unwrap();
+        assert_eq!(hot_seat.winner(), None);
+        hot_seat.place(3, 0, 4).unwrap();
+        assert_eq!(hot_seat.winner(), Some(Player::Placer));
+    }
+
+    #[test]
+    fn test_random_actions_are_legal() {
+        let mut rng = rand::thread_rng();
+        let mut hot_seat = HotSeatGame::new(Game::with_seed(5));
+
+        while let Some(action) = hot_seat.random_action(&mut rng) {
+            assert_eq!(matches!(action, Action::Slide(_)), hot_seat.to_act() == Player::Slider);
+            hot_seat.play(action).unwrap();
+        }
+
+        assert!(hot_seat.winner().is_some());
+    }
+}
@@ -0,0 +1,273 @@
+use gloo::timers::callback::Interval;
+use rust_2048::hot_seat::{Action, HotSeatError, HotSeatGame, Player};
+use rust_2048::notation::Turn;
+use rust_2048::Game;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+use crate::*;
+
+// Seconds each player has for their turn before a random one is played for them.
+const TURN_SECONDS: u32 = 10;
+
+const STATUS_SELECTOR: &str = ".hot-seat-status";
+
+pub enum HotSeatCommand {
+    /// `KeyboardEvent.code` of a key pressed anywhere on the page.
+    Key(String),
+    /// A click on the board, in pixels from its top left corner.
+    Click(f64, f64),
+    /// Value of the tiles placed by player B.
+    SetValue(u32),
+    /// Sent by the turn timer every second.
+    Tick,
+    /// Starts a new match, replacing the one in progress.
+    Rematch,
+    /// Sent when the hot-seat screen is unmounted.
+    Close,
+}
+
+/// The game between the two players and the time left for the current turn.
+struct Match {
+    hot_seat: HotSeatGame,
+    value: u32,
+    remaining: u32,
+    // The clock stops when the timer is dropped.
+    timer: Option<Interval>,
+}
+
+impl Match {
+    fn new(command_tx: UnboundedSender<HotSeatCommand>) -> Self {
+        let mut hot_seat_match = Match {
+            hot_seat: HotSeatGame::new(Game::new()),
+            value: 2,
+            remaining: TURN_SECONDS,
+            timer: None,
+        };
+
+        hot_seat_match.start_clock(command_tx);
+        hot_seat_match
+    }
+
+    fn start_clock(&mut self, command_tx: UnboundedSender<HotSeatCommand>) {
+        self.remaining = TURN_SECONDS;
+        self.timer = Some(Interval::new(1000, move || {
+            command_tx.send(HotSeatCommand::Tick).unwrap();
+        }));
+    }
+
+    /// Slides the tiles for player A.
+    async fn slide(&mut self, direction: Direction, animationend_rx: &mut counted_channel::CountedReceiver) {
+        match self.hot_seat.slide(direction) {
+            Ok(tiles) => {
+                animate_move(&board_container(), &tiles, None, animationend_rx).await;
+                self.end_turn();
+            },
+            Err(HotSeatError::NotYourTurn(_)) => set_status(STATUS_SELECTOR, "Player B has to place a tile first."),
+            Err(_) => (),
+        }
+    }
+
+    /// Places a tile for player B.
+    fn place(&mut self, row: usize, col: usize, value: u32) {
+        match self.hot_seat.place(row, col, value) {
+            Ok(id) => {
+                add_tile(&board_container(), get_tile_by_id(&self.hot_seat.game().get_tiles(), id).expect("Failed to find new Tile."));
+                self.end_turn();
+            },
+            Err(HotSeatError::NotYourTurn(_)) => set_status(STATUS_SELECTOR, "Player A has to slide first."),
+            Err(HotSeatError::Occupied { .. }) => set_status(STATUS_SELECTOR, "Tiles can only be placed on empty cells."),
+            Err(_) => (),
+        }
+    }
+
+    /// Plays a random turn for the player who ran out of time.
+    async fn time_out(&mut self, animationend_rx: &mut counted_channel::CountedReceiver) {
+        let Some(action) = self.hot_seat.random_action(&mut rand::thread_rng()) else {
+            return
+        };
+
+        match action {
+            Action::Slide(direction) => self.slide(direction, animationend_rx).await,
+            Action::Place(spawn) => self.place(spawn.row, spawn.col, spawn.value),
+        }
+    }
+
+    fn end_turn(&mut self) {
+        self.remaining = TURN_SECONDS;
+        update_score(self.hot_seat.game().score);
+
+        match self.hot_seat.winner() {
+            Some(Player::Slider) => {
+                self.timer = None;
+                set_status(STATUS_SELECTOR, &format!("Player A wins by reaching {}!", self.hot_seat.game().config().goal_tile));
+            },
+            Some(Player::Placer) => {
+                self.timer = None;
+                set_status(STATUS_SELECTOR, "Player B wins, player A has no moves left!");
+            },
+            None => set_status(STATUS_SELECTOR, ""),
+        }
+    }
+
+    /// Brings the turn indicator, clock and value buttons in line with the match.
+    fn update_controls(&self) {
+        let document = gloo::utils::document();
+
+        let turn = match (self.hot_seat.winner(), self.hot_seat.turn()) {
+            (Some(_), _) => "Game over",
+            (None, Turn::Slide) => "Player A: slide",
+            (None, Turn::Spawn) => "Player B: place",
+        };
+
+        let indicator = document.query_selector(".turn-player").unwrap().unwrap();
+        indicator.set_inner_html(turn);
+
+        let clock = document.query_selector(".turn-timer").unwrap().unwrap();
+        clock.set_inner_html(&match self.timer {
+            Some(_) => format!("{}s", self.remaining),
+            None => String::new(),
+        });
+
+        for value in [2, 4] {
+            let button = document.query_selector(&format!(".place-value-{}", value)).unwrap().unwrap();
+            button.class_list().toggle_with_force("selected", value == self.value).unwrap();
+        }
+    }
+}
+
+async fn process_hot_seat_commands(mut command_rx: UnboundedReceiver<HotSeatCommand>, command_tx: UnboundedSender<HotSeatCommand>, mut animationend_rx: counted_channel::CountedReceiver) {
+    let mut hot_seat_match = Match::new(command_tx.clone());
+
+    while let Some(command) = command_rx.recv().await {
+        match command {
+            HotSeatCommand::Key(code) => match code.as_str() {
+                "Digit2" | "Numpad2" => hot_seat_match.value = 2,
+                "Digit4" | "Numpad4" => hot_seat_match.value = 4,
+                code => if let Some(direction) = Direction::from_key(code) {
+                    hot_seat_match.slide(direction, &mut animationend_rx).await;
+                },
+            },
+            HotSeatCommand::Click(x, y) => {
+                if let (Some(row), Some(col)) = (slot_at(y), slot_at(x)) {
+                    let value = hot_seat_match.value;
+                    hot_seat_match.place(row, col, value);
+                }
+            },
+            HotSeatCommand::SetValue(value) => hot_seat_match.value = value,
+            // Ticks that were already queued when the game ended are ignored.
+            HotSeatCommand::Tick => {
+                if hot_seat_match.timer.is_some() {
+                    hot_seat_match.remaining = hot_seat_match.remaining.saturating_sub(1);
+
+                    if hot_seat_match.remaining == 0 {
+                        hot_seat_match.time_out(&mut animationend_rx).await;
+                    }
+                }
+            },
+            HotSeatCommand::Rematch => {
+                hot_seat_match = Match::new(command_tx.clone());
+                render_board(&board_container(), hot_seat_match.hot_seat.game());
+                update_score(0);
+                set_status(STATUS_SELECTOR, "");
+            },
+            HotSeatCommand::Close => break,
+        }
+
+        hot_seat_match.update_controls();
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct HotSeatProps {
+    pub close_callback: Callback<MouseEvent>,
+}
+
+/// Two players sharing one board: player A slides the tiles with the keyboard and player B places
+/// every new tile by clicking an empty cell. See `rust_2048::hot_seat` for the rules.
+#[function_component(HotSeat)]
+pub fn hot_seat(props: &HotSeatProps) -> Html {
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (animationend_tx, animationend_rx) = counted_channel::CountedChannel::new();
+
+    spawn_local(process_hot_seat_commands(command_rx, command_tx.clone(), animationend_rx));
+
+    {
+        let command_tx = command_tx.clone();
+
+        use_effect(move || {
+            // The first board is dealt once the empty board has been rendered.
+            command_tx.send(HotSeatCommand::Rematch).unwrap();
+
+            let key_tx = command_tx.clone();
+            let remove_listeners = add_game_listeners(Some(Callback::from(move |code| key_tx.send(HotSeatCommand::Key(code)).unwrap())), animationend_tx);
+
+            move || {
+                remove_listeners();
+                command_tx.send(HotSeatCommand::Close).unwrap();
+            }
+        });
+    }
+
+    let click_callback = {
+        let command_tx = command_tx.clone();
+
+        Callback::from(move |event: MouseEvent| {
+            let board = event.current_target().unwrap();
+            let bounds = board.dyn_ref::<Element>().unwrap().get_bounding_client_rect();
+
+            command_tx.send(HotSeatCommand::Click(event.client_x() as f64 - bounds.left(), event.client_y() as f64 - bounds.top())).unwrap();
+        })
+    };
+
+    let send = |command: fn() -> HotSeatCommand| {
+        let command_tx = command_tx.clone();
+        Callback::from(move |_: MouseEvent| command_tx.send(command()).unwrap())
+    };
+
+    let two_callback = send(|| HotSeatCommand::SetValue(2));
+    let four_callback = send(|| HotSeatCommand::SetValue(4));
+    let rematch_callback = send(|| HotSeatCommand::Rematch);
+
+    let style_args = button_style_args();
+    let status_style = format!("--status_text: {}", COLORS.text_light);
+
+    html! {
+        <div class="content hot-seat noselect">
+            <div class="metadata-container">
+                <Score score={0}/>
+                <div class="metadata turn-indicator" style={style_args.clone()}>
+                    <span class="turn-player">{ "Player A: slide" }</span>
+                    <span class="turn-timer">{ format!("{}s", TURN_SECONDS) }</span>
+                </div>
+                <NewGameButton onclick={props.close_callback.clone()} button_text={"Back to Game"} disabled={false}/>
+            </div>
+            <div class="board-container" onclick={click_callback}>
+                <GameBoard/>
+            </div>
+            <div class="hot-seat-controls">
+                <button class="metadata place-value-2 selected" onclick={two_callback} style={style_args.clone()}>{ "Place 2" }</button>
+                <button class="metadata place-value-4" onclick={four_callback} style={style_args.clone()}>{ "Place 4" }</button>
+                <button class="metadata" onclick={rematch_callback} style={style_args}>{ "Rematch" }</button>
+            </div>
+            <div class="hot-seat-status" style={status_style}/>
+        </div>
+    }
+}
+
+// Helper functions
+
+/// Row or column of the cell at `offset` pixels from the board's edge, or `None` for the border
+/// between cells.
+fn slot_at(offset: f64) -> Option<usize> {
+    let offset = offset - BORDER_SPACING as f64;
+    let pitch = (TILE_DIMENSION + BORDER_SPACING) as f64;
+
+    if offset < 0.0 || offset % pitch >= TILE_DIMENSION as f64 {
+        return None
+    }
+
+    Some((offset / pitch) as usize).filter(|&slot| slot < BOARD_DIMENSION)
+}
@@ -1,8 +1,30 @@
-use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom};
+use rand::{distributions::WeightedIndex, prelude::Distribution, seq::SliceRandom, Rng};
 use std::collections::LinkedList;
 use hex_color::HexColor;
+use serde::{Deserialize, Serialize};
+use spawn::{Difficulty, RandomSpawner, SeededSpawner, Spawn, SpawnProvider, Spawner};
 
 mod counted_channel;
+mod parallel;
+mod rng;
+pub mod ai;
+pub mod ai_worker;
+pub mod challenge;
+pub mod ghost;
+pub mod hot_seat;
+pub mod metrics;
+pub mod replay;
+pub mod notation;
+pub mod ntuple;
+pub mod original_state;
+pub mod race_server;
+pub mod score_server;
+pub mod spawn;
+pub mod strategy;
+pub mod tournament;
+pub mod versus;
+pub mod transposition;
+pub mod tuner;
 
 pub const BOARD_DIMENSION: usize = 4;
 const NUM_TILES: usize = BOARD_DIMENSION * BOARD_DIMENSION;
@@ -15,7 +37,8 @@ pub struct Tile {
     pub text_color: String,
     pub row: usize,
     pub col: usize,
-    pub merged: Option<Box<Tile>>,
+    /// Clones of the tiles merged into this one on the last move, at the slot they merged in.
+    pub merged: Vec<Tile>,
 }
 
 impl Tile {
@@ -27,7 +50,7 @@ impl Tile {
             text_color,
             row,
             col,
-            merged: None,
+            merged: Vec::new(),
         }
     }
 }
@@ -99,10 +122,310 @@ impl Colors {
     }
 }
 
+/// The directions in which the tiles on the board can be slid. Square boards use the first four,
+/// hex boards six; see `Topology`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpRight,
+    DownLeft,
+}
+
+impl Direction {
+    /// The directions of the square board.
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    /// Maps a `KeyboardEvent.code` to a direction on the square board. Arrow keys, WASD and HJKL
+    /// are all accepted. See `Topology::direction_for_key()` for other boards.
+    pub fn from_key(key: &str) -> Option<Direction> {
+        match key {
+            "ArrowUp" | "KeyK" | "KeyW" => Some(Direction::Up),
+            "ArrowDown" | "KeyJ" | "KeyS" => Some(Direction::Down),
+            "ArrowLeft" | "KeyH" | "KeyA" => Some(Direction::Left),
+            "ArrowRight" | "KeyL" | "KeyD" => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Single letter used for directions in replays and other compact text formats. The hex
+    /// board's diagonals are named after their keys.
+    pub fn letter(self) -> char {
+        match self {
+            Direction::Up => 'U',
+            Direction::Down => 'D',
+            Direction::Left => 'L',
+            Direction::Right => 'R',
+            Direction::UpRight => 'E',
+            Direction::DownLeft => 'A',
+        }
+    }
+
+    /// Inverse of `letter()`.
+    pub fn from_letter(letter: char) -> Option<Direction> {
+        Topology::Hex.directions().iter().copied().find(|direction| direction.letter() == letter)
+    }
+
+    /// Row and column offsets of the neighbouring slot in this direction.
+    pub fn step(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+            Direction::Right => (0, 1),
+            Direction::UpRight => (-1, 1),
+            Direction::DownLeft => (1, -1),
+        }
+    }
+}
+
+/// Shape of the board's cells, which decides which slots are neighbours and so in which
+/// directions the tiles can slide. Every topology uses the same grid of slots.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Topology {
+    /// Square cells with four neighbours each.
+    #[default]
+    Square,
+    /// Flat-topped hexagonal cells laid out as a rhombus, with six neighbours each. Every column
+    /// sits half a cell lower than the one to its left, so that `Left` and `Right` slide to the
+    /// upper left and lower right, and `UpRight` and `DownLeft` along the other diagonal.
+    Hex,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 2] = [Topology::Square, Topology::Hex];
+
+    /// Directions in which the tiles can slide.
+    pub fn directions(self) -> &'static [Direction] {
+        match self {
+            Topology::Square => &Direction::ALL,
+            Topology::Hex => &[Direction::Up, Direction::UpRight, Direction::Right, Direction::Down, Direction::DownLeft, Direction::Left],
+        }
+    }
+
+    /// Maps a `KeyboardEvent.code` to a direction. Hex boards are played with QWE and ASD, laid
+    /// out like the six neighbours of a cell, and the up and down arrows.
+    pub fn direction_for_key(self, key: &str) -> Option<Direction> {
+        match self {
+            Topology::Square => Direction::from_key(key),
+            Topology::Hex => match key {
+                "KeyQ" => Some(Direction::Left),
+                "KeyW" | "ArrowUp" => Some(Direction::Up),
+                "KeyE" => Some(Direction::UpRight),
+                "KeyA" => Some(Direction::DownLeft),
+                "KeyS" | "ArrowDown" => Some(Direction::Down),
+                "KeyD" => Some(Direction::Right),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Square => "Square",
+            Topology::Hex => "Hex",
+        }
+    }
+
+    /// The neighbour of a slot in `direction`, or `None` at the edge of the board.
+    pub fn neighbour(self, row: usize, col: usize, direction: Direction) -> Option<(usize, usize)> {
+        if !self.directions().contains(&direction) {
+            return None
+        }
+
+        let (row_step, col_step) = direction.step();
+        let row = row.checked_add_signed(row_step).filter(|&row| row < BOARD_DIMENSION)?;
+        let col = col.checked_add_signed(col_step).filter(|&col| col < BOARD_DIMENSION)?;
+
+        Some((row, col))
+    }
+
+    /// The lines of slots along which tiles slide in `direction`, each starting from the edge the
+    /// tiles slide towards. Lines are listed in row-major order of the slot they start from.
+    pub fn lines(self, direction: Direction) -> Vec<Vec<(usize, usize)>> {
+        let mut lines = Vec::new();
+
+        if !self.directions().contains(&direction) {
+            return lines
+        }
+
+        let opposite = self.directions().iter().copied()
+            .find(|other| other.step() == (-direction.step().0, -direction.step().1))
+            .expect("Every direction has an opposite.");
+
+        for row in 0..BOARD_DIMENSION {
+            for col in 0..BOARD_DIMENSION {
+                if self.neighbour(row, col, direction).is_some() {
+                    continue
+                }
+
+                let mut line = vec![(row, col)];
+                let mut slot = (row, col);
+
+                while let Some(next) = self.neighbour(slot.0, slot.1, opposite) {
+                    line.push(next);
+                    slot = next;
+                }
+
+                lines.push(line);
+            }
+        }
+
+        lines
+    }
+}
+
+/// What happens to tiles that slide up to the edge of the board.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Edges {
+    /// Tiles stop at the edge of the board.
+    #[default]
+    Bounded,
+    /// Tiles sliding past an edge come back in from the opposite one, so that every line is a
+    /// ring. Tiles slide in turn from the edge the tiles slide towards, each one going on round the
+    /// ring until it meets another tile. A tile that is alone on its line has nothing to stop it and
+    /// stays put, so a move that only has lone tiles to slide is invalid.
+    Wrapped,
+}
+
+impl Edges {
+    pub const ALL: [Edges; 2] = [Edges::Bounded, Edges::Wrapped];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Edges::Bounded => "Bounded",
+            Edges::Wrapped => "Wrapped",
+        }
+    }
+}
+
+/// How tiles combine when one slides into another.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeRule {
+    /// Equal tiles merge into one of twice their value: 2, 4, 8, ...
+    #[default]
+    Doubling,
+    /// Consecutive Fibonacci numbers merge into their sum, as do two 1s: 1, 2, 3, 5, 8, ...
+    Fibonacci,
+    /// Three equal tiles merge into one of three times their value: 3, 9, 27, ...
+    Tripling,
+}
+
+impl MergeRule {
+    pub const ALL: [MergeRule; 3] = [MergeRule::Doubling, MergeRule::Fibonacci, MergeRule::Tripling];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MergeRule::Doubling => "Doubling",
+            MergeRule::Fibonacci => "Fibonacci",
+            MergeRule::Tripling => "Tripling",
+        }
+    }
+
+    /// Number of tiles that merge into one.
+    pub fn tiles_per_merge(self) -> usize {
+        match self {
+            MergeRule::Doubling | MergeRule::Fibonacci => 2,
+            MergeRule::Tripling => 3,
+        }
+    }
+
+    /// Value of the tile that tiles of `values` merge into, or `None` if they do not merge. The
+    /// values are listed from the tile furthest ahead, and there must be `tiles_per_merge()` of them.
+    pub fn merge(self, values: &[u32]) -> Option<u32> {
+        if values.len() != self.tiles_per_merge() {
+            return None
+        }
+
+        match self {
+            MergeRule::Doubling => (values[0] == values[1]).then(|| values[0] * 2),
+            MergeRule::Fibonacci => {
+                let (small, large) = (values[0].min(values[1]), values[0].max(values[1]));
+                let consecutive = (small == 1 && large == 1) || fibonacci_rank(small).is_some_and(|rank| fibonacci_rank(large) == Some(rank + 1));
+
+                consecutive.then(|| small + large)
+            },
+            MergeRule::Tripling => values.iter().all(|&value| value == values[0]).then(|| values[0] * 3),
+        }
+    }
+
+    /// Values a new tile can take, the more common first.
+    pub fn spawn_values(self) -> [u32; 2] {
+        match self {
+            MergeRule::Doubling => [2, 4],
+            MergeRule::Fibonacci => [1, 2],
+            MergeRule::Tripling => [3, 9],
+        }
+    }
+
+    /// Tile that wins the game when first reached: 2048, or the tile closest to it under rules
+    /// that never reach it.
+    pub fn goal_tile(self) -> u32 {
+        match self {
+            MergeRule::Doubling => Game::WINNING_TILE,
+            MergeRule::Fibonacci => 2584,
+            MergeRule::Tripling => 2187,
+        }
+    }
+
+    /// Position of a tile value among the values that can be reached under this rule, counting
+    /// from 1: the base 2 logarithm of doubled tiles, the base 3 logarithm of tripled tiles, and 1,
+    /// 2, 3, 4 for Fibonacci tiles of 1, 2, 3 and 5. Values that cannot be reached have rank 0.
+    pub fn rank(self, value: u32) -> u32 {
+        match self {
+            MergeRule::Doubling => log_2(value),
+            MergeRule::Fibonacci => fibonacci_rank(value).unwrap_or(0),
+            MergeRule::Tripling => value.checked_ilog(3).unwrap_or(0),
+        }
+    }
+}
+
+/// Rules a game is played with. Recorded in replays so that they are only ever played back under
+/// the rules they were recorded with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GameConfig {
+    pub board_size: usize,
+    /// Values a new tile can take, with the relative weight of each.
+    pub spawn_values: [u32; 2],
+    pub spawn_weights: [u8; 2],
+    /// Tile that wins the game when first reached.
+    pub goal_tile: u32,
+    /// Recordings made before difficulties existed were all normal games.
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Recordings made before hex boards existed were all played on square boards.
+    #[serde(default)]
+    pub topology: Topology,
+    /// Recordings made before wrapped boards existed all had bounded edges.
+    #[serde(default)]
+    pub edges: Edges,
+    /// Recordings made before merge rules existed were all played with doubling tiles.
+    #[serde(default)]
+    pub merge_rule: MergeRule,
+}
+
+impl Default for GameConfig {
+    /// The classic rules at normal difficulty.
+    fn default() -> GameConfig {
+        Game::empty().config()
+    }
+}
+
+/// Base 2 logarithms of the tiles on a board, with 0 representing an empty slot. This compact form
+/// is what the AI modules work with. Boards with other merge rules hold the `MergeRule::rank()` of
+/// each tile instead.
+pub type ExponentGrid = [[u8; BOARD_DIMENSION]; BOARD_DIMENSION];
+
 pub struct InvalidMove;
 
+/// The ID of the spawned tile is `None` if the game's spawn provider spawned nothing.
 pub enum InputResult<'a> {
-    Ok(usize, Vec<&'a Tile>, bool),
+    Ok(Option<usize>, Vec<&'a Tile>, bool),
     Err(InvalidMove),
 }
 
@@ -110,13 +433,16 @@ pub enum InputResult<'a> {
 pub struct Game {
     pub board: [[Option<Tile>; BOARD_DIMENSION]; BOARD_DIMENSION],
     new_tile_params: NewTileParams,
-    free_slots: Vec<(usize, usize)>,
     pub score: u32,
     id_list: LinkedList<usize>,
     game_won: bool, // Will be initialized to false, but the frontend will have 
                         // the freedom to set this to `true` depending on when a
                         // certain tile value is reached. This means that 2048 does
                         // not strictly need to be the winning tile.
+    spawner: Spawner,
+    topology: Topology,
+    edges: Edges,
+    merge_rule: MergeRule,
 }
 
 impl Game {
@@ -128,70 +454,160 @@ impl Game {
     const WINNING_TILE: u32 = 2048;
 
     pub fn new() -> Game {
-        const EMPTY_TILE: Option<Tile> = None;
-        const EMPTY_ROW: [Option<Tile>; BOARD_DIMENSION] = [EMPTY_TILE; BOARD_DIMENSION];
-        
-        // Tile IDs will be recycled, but we are making the number of available IDs 1 greater than
-        // the maximum number of tiles. This is because a new tile should not recycle an ID from a
-        // tile that was just merged on the current turn. The edge case here is the entire board is
-        // occupied with 16 tiles but a player move is still possible; in this case the new tile
-        // created after this move will need a 17th ID to use.
-        let tile_ids: [usize; NUM_TILES + 1] = std::array::from_fn(|i| i as usize);
+        Game::with_spawner(Box::new(RandomSpawner))
+    }
 
-        let mut game = Game {
-            board: [EMPTY_ROW; BOARD_DIMENSION],
-            new_tile_params: NewTileParams::new(),
-            free_slots: Vec::with_capacity(BOARD_DIMENSION * BOARD_DIMENSION),
-            score: 0,
-            id_list: LinkedList::from(tile_ids),
-            game_won: false,
-        };
+    /// Same as `new()`, except that every random choice is drawn from a generator seeded with
+    /// `seed`. Two games with the same seed that receive the same moves are identical.
+    pub fn with_seed(seed: u64) -> Game {
+        Game::with_spawner(Box::new(SeededSpawner::new(seed)))
+    }
+
+    /// Starts a new game whose starting tiles and spawns are all chosen by `spawner`.
+    pub fn with_spawner(spawner: Box<dyn SpawnProvider>) -> Game {
+        Game::with_merge_rule(spawner, MergeRule::Doubling)
+    }
+
+    /// Same as `with_spawner()` for a game whose tiles merge under `merge_rule`, and so spawn with
+    /// its values.
+    pub fn with_merge_rule(spawner: Box<dyn SpawnProvider>, merge_rule: MergeRule) -> Game {
+        let mut game = Game::empty();
+        game.spawner = Spawner(spawner);
+        game.merge_rule = merge_rule;
+        game.new_tile_params.tile_choices = merge_rule.spawn_values();
+
+        for spawn in game.use_spawner(|spawner, game| spawner.opening(game)) {
+            game.place_tile(spawn.row, spawn.col, spawn.value);
+        }
+
+        game
+    }
+
+    /// Returns the provider of all future spawns.
+    pub fn spawner(&self) -> &dyn SpawnProvider {
+        self.spawner.0.as_ref()
+    }
+
+    /// Replaces the provider of all future spawns.
+    pub fn set_spawner(&mut self, spawner: Box<dyn SpawnProvider>) {
+        self.spawner = Spawner(spawner);
+    }
+
+    /// Replaces the provider of all future spawns with random spawns seeded by `seed`.
+    ///
+    /// AI players use this on copies of a seeded game so that simulations cannot see the spawns
+    /// the real game is going to produce.
+    pub fn reseed(&mut self, seed: u64) {
+        self.set_spawner(Box::new(SeededSpawner::new(seed)));
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Changes the shape of the board's cells, and with it the directions the tiles can slide in.
+    /// The tiles stay in their slots.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn edges(&self) -> Edges {
+        self.edges
+    }
+
+    /// Changes whether tiles stop at the edges of the board or wrap around to the opposite edge.
+    pub fn set_edges(&mut self, edges: Edges) {
+        self.edges = edges;
+    }
+
+    pub fn merge_rule(&self) -> MergeRule {
+        self.merge_rule
+    }
+
+    /// Lends the spawn provider out together with the game it spawns on.
+    fn use_spawner<T>(&mut self, f: impl FnOnce(&mut dyn SpawnProvider, &Game) -> T) -> T {
+        let mut spawner = std::mem::replace(&mut self.spawner.0, Box::new(spawn::NoSpawner));
+        let result = f(spawner.as_mut(), self);
+        self.spawner.0 = spawner;
 
+        result
+    }
+
+    /// Chooses the two starting tiles of a normal game from `rng`, both in random free slots.
+    pub(crate) fn random_opening<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<Spawn> {
         // If first tile is 4, second tile must be 2.
         // If first tile is 2, second tile may either be 2 or 4.
-        let first_tile_value = game.generate_tile_value();
+        let first_tile_value = self.generate_tile_value(rng);
         let second_tile_value;
         
-        if first_tile_value == game.new_tile_params.tile_choices[NewTileParams::FOUR] {
-            second_tile_value = game.new_tile_params.tile_choices[NewTileParams::TWO];
+        if first_tile_value == self.new_tile_params.tile_choices[NewTileParams::FOUR] {
+            second_tile_value = self.new_tile_params.tile_choices[NewTileParams::TWO];
         } else {
-            second_tile_value = game.generate_tile_value();
+            second_tile_value = self.generate_tile_value(rng);
         }
 
-        // let first_tile_value = 16384;
-        // let second_tile_value = 131072;
-        // let first_tile_value = 2048;
-        // let second_tile_value = 1024;
+        let mut board = self.clone();
+        let mut spawns = Vec::with_capacity(2);
 
-        let first_tile_pos = game.get_random_free_slot().expect("New game board, should not panic.");
-        let first_tile_id = game.get_id().unwrap();
-        let (background_color, text_color) = game.get_tile_colors(first_tile_value);
+        for value in [first_tile_value, second_tile_value] {
+            let (row, col) = board.get_random_free_slot(rng).expect("New game board, should not panic.");
+            board.place_tile(row, col, value);
+            spawns.push(Spawn { row, col, value });
+        }
 
-        let first_tile = Tile::new(first_tile_value,
-                                   first_tile_id, 
-                                   background_color,
-                                   text_color,
-                                   first_tile_pos.0,
-                                   first_tile_pos.1);
+        spawns
+    }
 
-        game.board[first_tile_pos.0][first_tile_pos.1] = Some(first_tile);
-        
-        let second_tile_pos = game.get_random_free_slot().expect("New game board, should not panic.");
-        let second_tile_id = game.get_id().unwrap();
-        let (background_color, text_color) = game.get_tile_colors(second_tile_value);
+    /// Chooses a random tile value in a random free slot from `rng`, as in a normal game.
+    pub(crate) fn random_spawn<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Spawn> {
+        let (row, col) = self.get_random_free_slot(rng)?;
+        let value = self.generate_tile_value(rng);
 
-        let second_tile = Tile::new(second_tile_value,
-                                    second_tile_id,
-                                    background_color,
-                                    text_color,
-                                    second_tile_pos.0,
-                                    second_tile_pos.1);
+        Some(Spawn { row, col, value })
+    }
+
+    /// Builds a game from a grid of base 2 exponents, with 0 representing an empty slot. The score
+    /// starts at 0. Useful for setting up specific positions in analysis tools; tests and players
+    /// can write positions more readably with `Game::from_notation()`.
+    pub fn from_exponents(exponents: &ExponentGrid) -> Game {
+        let mut game = Game::empty();
 
-        game.board[second_tile_pos.0][second_tile_pos.1] = Some(second_tile);
+        for (row, row_exponents) in exponents.iter().enumerate() {
+            for (col, &exponent) in row_exponents.iter().enumerate() {
+                if exponent > 0 {
+                    game.place_tile(row, col, 1 << exponent);
+                }
+            }
+        }
 
         game
     }
 
+    /// Returns a game with no tiles on the board.
+    fn empty() -> Game {
+        const EMPTY_TILE: Option<Tile> = None;
+        const EMPTY_ROW: [Option<Tile>; BOARD_DIMENSION] = [EMPTY_TILE; BOARD_DIMENSION];
+
+        // Tile IDs will be recycled, but we are making the number of available IDs 1 greater than
+        // the maximum number of tiles. This is because a new tile should not recycle an ID from a
+        // tile that was just merged on the current turn. The edge case here is the entire board is
+        // occupied with 16 tiles but a player move is still possible; in this case the new tile
+        // created after this move will need a 17th ID to use.
+        let tile_ids: [usize; NUM_TILES + 1] = std::array::from_fn(|i| i as usize);
+
+        Game {
+            board: [EMPTY_ROW; BOARD_DIMENSION],
+            new_tile_params: NewTileParams::new(),
+            score: 0,
+            id_list: LinkedList::from(tile_ids),
+            game_won: false,
+            spawner: Spawner(Box::new(RandomSpawner)),
+            topology: Topology::Square,
+            edges: Edges::Bounded,
+            merge_rule: MergeRule::Doubling,
+        }
+    }
+
     /// Returns the next available ID. Will return None if all IDs are used.
     fn get_id(&mut self) -> Option<usize> {
         self.id_list.pop_front()
@@ -206,26 +622,10 @@ impl Game {
 
     /// Generates a new tile - either 2 or 4 according to the weights defined in
     /// `self.new_tile_params`
-    fn generate_tile_value(&self) -> u32 {
-        let mut rng = rand::thread_rng();
+    fn generate_tile_value<R: Rng + ?Sized>(&self, rng: &mut R) -> u32 {
         let dist = WeightedIndex::new(self.new_tile_params.tile_weights).unwrap();
 
-        let tile = self.new_tile_params.tile_choices[dist.sample(&mut rng)];
-
-        tile
-    }
-
-    /// Updates the list of free slots.
-    fn update_free_slots(&mut self) {
-        self.free_slots.clear();
-
-        for row in 0..BOARD_DIMENSION {
-            for col in 0..BOARD_DIMENSION {
-                if let None = self.board[row][col] {
-                    self.free_slots.push((row, col));
-                }
-            }
-        }
+        self.new_tile_params.tile_choices[dist.sample(rng)]
     }
 
     /// Returns a vec of all current tiles.
@@ -245,12 +645,8 @@ impl Game {
 
     /// Returns the coordinates of a free board slot at random. 
     /// Will return `None` if no free slots exist, indicating the game is over.
-    fn get_random_free_slot(&mut self) -> Option<(usize, usize)> {
-        self.update_free_slots();
-
-        let mut rng = rand::thread_rng();
-
-        self.free_slots.choose(&mut rng).copied()
+    fn get_random_free_slot<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(usize, usize)> {
+        self.empty_slots().choose(rng).copied()
     }
 
     /// Prints a text representation of the game board to stdout.
@@ -266,12 +662,12 @@ impl Game {
         }
     }
 
-    /// Sets the `merged` field to false for all Tiles before any move is calculated.
+    /// Clears the `merged` field of all Tiles before any move is calculated.
     fn reset_merged_flags(&mut self) {
         for row in 0..BOARD_DIMENSION {
             for col in 0..BOARD_DIMENSION {
                 if self.board[row][col].is_some() {
-                    self.board[row][col].as_mut().unwrap().merged = None;
+                    self.board[row][col].as_mut().unwrap().merged.clear();
                 }
             }
         }
@@ -284,12 +680,8 @@ impl Game {
     /// The frontend will be responsible to determining if the game is won. See `Game` struct
     /// definition for more details.
     pub fn game_over(&self) -> bool {
-        let directions = ["ArrowUp", "ArrowDown", "ArrowLeft", "ArrowRight"];
-
-        for direction in directions {
-            let mut current_game = self.clone();
-
-            if let InputResult::Ok(_, _, _) = current_game.receive_input(direction) {
+        for &direction in self.topology.directions() {
+            if self.afterstate(direction).is_some() {
                 return false
             }
         }
@@ -297,200 +689,275 @@ impl Game {
         true
     }
 
-    /// Receives the user's input and slides tiles in the specified direction.
+    /// Receives the user's input and slides tiles in the direction the key stands for on this
+    /// board.
     pub fn receive_input(&mut self, input: &str) -> InputResult {
-        let mut move_occurred = false;
-        let mut recycled_ids: Vec<usize> = Vec::new();
-        self.reset_merged_flags();
+        match self.topology.direction_for_key(input) {
+            Some(direction) => self.make_move(direction),
+            None => InputResult::Err(InvalidMove),
+        }
+    }
 
-        let mut winning_tile_reached = false;
+    /// Slides tiles in the specified direction and spawns a new tile if any tile moved.
+    pub fn make_move(&mut self, direction: Direction) -> InputResult<'_> {
+        let (move_occurred, recycled_ids, winning_tile_reached) = self.slide(direction);
 
-        // i in the loops below represents the index difference between the Tile's starting slot
-        // and its destination slot.
-        // i will be incremented each time the Tile is shifted by one slot and until it can 
-        // no longer be shifted.
-        match input {
-            "ArrowUp" | "KeyK" | "KeyW" => {
-                for col in 0..BOARD_DIMENSION {
-                    for row in 1..BOARD_DIMENSION {
-                        let mut i = 1;
-
-                        if let Some(mut tile) = self.board[row][col].take() {
-                            // Loop until an occupied cell is found.
-                            while row.checked_sub(i).is_some_and(|diff| self.board[diff][col].is_none()) {
-                                i += 1;
-                            }
-
-                            // If subtraction causes an underflow, there is no tile between current
-                            // tile and the board edge; slide the current tile to its destination.
-
-                            // If no underflow occurs, there must be another tile present: perform
-                            // merging logic.
-
-                            // Double merges should not be allowed e.g. [2, 2, 2, 2] -> [0, 0, 4, 4] is a correct merge.
-                            if row.checked_sub(i).is_some_and(|diff| self.board[diff][col].as_ref().unwrap().value == tile.value && self.board[diff][col].as_ref().unwrap().merged.is_none()) {
-                                let removed_tile = self.board[row - i][col].take().unwrap();
-
-                                winning_tile_reached = self.merge_tiles(&mut tile, removed_tile.clone(), &mut recycled_ids);
-                                self.update_tile_and_board(tile, removed_tile.row, removed_tile.col);
-                                move_occurred = true;
-                            } else {
-                                self.update_tile_and_board(tile, row - (i - 1), col);
-
-                                if i > 1 {
-                                    move_occurred = true;
-                                }
-                            }
-                        }
-                    }
+        match move_occurred {
+            true => {
+                // New tile ID should not use the ID of a tile that was merged this turn.
+                let new_id = self.spawn_tile();
+                self.recycle_ids(recycled_ids);
+
+                // Game can only be won the first time a winning tile is reached.
+                let mut send_game_won = false;
+
+                if !self.game_won && winning_tile_reached {
+                    send_game_won = true;
+                    self.game_won = true;
                 }
+
+                InputResult::Ok(new_id, self.get_tiles(), send_game_won)
             },
-            "ArrowDown" | "KeyJ" | "KeyS" => {
-                for col in 0..BOARD_DIMENSION {
-                    for row in (0..BOARD_DIMENSION - 1).rev() {
-                        let mut i = 1;
-
-                        if let Some(mut tile) = self.board[row][col].take() {
-                            while row.checked_add_max(i, BOARD_DIMENSION).is_some_and(|sum| self.board[sum][col].is_none()) {
-                                i += 1;
-                            }
-
-                            // See comments for the "ArrowUp" case for an explanation of this merging logic
-                            if row.checked_add_max(i, BOARD_DIMENSION).is_some_and(|sum| self.board[sum][col].as_ref().unwrap().value == tile.value && self.board[sum][col].as_ref().unwrap().merged.is_none()) {
-                                let removed_tile = self.board[row + i][col].take().unwrap();
-
-                                winning_tile_reached = self.merge_tiles(&mut tile, removed_tile.clone(), &mut recycled_ids);
-                                self.update_tile_and_board(tile, removed_tile.row, removed_tile.col);
-                                move_occurred = true;
-                            } else {
-                                self.update_tile_and_board(tile, row + (i - 1), col);
-
-                                if i > 1 {
-                                    move_occurred = true;
-                                }
-                            }
-                        }
-                    }
+            false => InputResult::Err(InvalidMove),
+        }
+    }
+
+    /// Returns the board as it stands after sliding in `direction` but *before* a new tile is
+    /// spawned, along with the points scored by the slide. Returns `None` if nothing would move.
+    ///
+    /// These "afterstates" are what the AI evaluates, since the spawn that follows is random.
+    pub fn afterstate(&self, direction: Direction) -> Option<(Game, u32)> {
+        let mut game = self.clone();
+        let (move_occurred, recycled_ids, _) = game.slide(direction);
+
+        if !move_occurred {
+            return None
+        }
+
+        game.recycle_ids(recycled_ids);
+        let reward = game.score - self.score;
+
+        Some((game, reward))
+    }
+
+    /// Places the tile chosen by the game's spawn provider, returning its ID. Returns `None` if the
+    /// provider spawned nothing or chose an occupied slot.
+    pub fn spawn_tile(&mut self) -> Option<usize> {
+        let spawn = self.use_spawner(|spawner, game| spawner.next_spawn(game))?;
+
+        self.place_tile(spawn.row, spawn.col, spawn.value)
+    }
+
+    /// Places a tile of the given value in the specified slot, returning its ID. Returns `None` if
+    /// the slot is off the board or already occupied.
+    pub fn place_tile(&mut self, row: usize, col: usize, value: u32) -> Option<usize> {
+        if row >= BOARD_DIMENSION || col >= BOARD_DIMENSION || self.board[row][col].is_some() {
+            return None
+        }
+
+        let new_id = self.get_id().unwrap();
+        let (tile_background, tile_text) = self.get_tile_colors(value);
+
+        self.board[row][col] = Some(Tile::new(value, new_id, tile_background, tile_text, row, col));
+
+        Some(new_id)
+    }
+
+    /// Returns the coordinates of every empty slot in row-major order.
+    pub fn empty_slots(&self) -> Vec<(usize, usize)> {
+        let mut slots = Vec::new();
+
+        for row in 0..BOARD_DIMENSION {
+            for col in 0..BOARD_DIMENSION {
+                if self.board[row][col].is_none() {
+                    slots.push((row, col));
                 }
             }
-            "ArrowLeft" | "KeyH" | "KeyA" => {
-                for row in 0..BOARD_DIMENSION {
-                    for col in 1..BOARD_DIMENSION {
-                        let mut i = 1;
-
-                        if let Some(mut tile) = self.board[row][col].take() {
-                            while col.checked_sub(i).is_some_and(|diff| self.board[row][diff].is_none()) {
-                                i += 1
-                            }
-
-                            // See comments for the "ArrowUp" case for an explanation of this merging logic
-                            if col.checked_sub(i).is_some_and(|diff| self.board[row][diff].as_ref().unwrap().value == tile.value && self.board[row][diff].as_ref().unwrap().merged.is_none()) {
-                                let removed_tile = self.board[row][col - i].take().unwrap();
-                                
-                                winning_tile_reached = self.merge_tiles(&mut tile, removed_tile.clone(), &mut recycled_ids);
-                                self.update_tile_and_board(tile, removed_tile.row, removed_tile.col);
-
-                                move_occurred = true;
-                                // TODO: update background color to reflect the new value
-                            } else {
-                                self.update_tile_and_board(tile, row, col - (i - 1));
-
-                                if i > 1 {
-                                    move_occurred = true;
-                                }
-                            }
-                        }
-                    }
-                }
-            },
-            "ArrowRight" | "KeyL" | "KeyD" => {
-                for row in 0..BOARD_DIMENSION {
-                    for col in (0..BOARD_DIMENSION - 1).rev() {
-                        if let Some(mut tile) = self.board[row][col].take() {
-                            let mut i = 1;
-
-                            while col.checked_add_max(i, BOARD_DIMENSION).is_some_and(|sum| self.board[row][sum].is_none()) {
-                                i += 1;
-                            }
-
-                            // See comments for the "ArrowUp" case for an explanation of this merging logic
-                            if col.checked_add_max(i, BOARD_DIMENSION).is_some_and(|sum| self.board[row][sum].as_ref().unwrap().value == tile.value && self.board[row][sum].as_ref().unwrap().merged.is_none()) {
-                                let removed_tile = self.board[row][col + i].take().unwrap();
-
-                                winning_tile_reached = self.merge_tiles(&mut tile, removed_tile.clone(), &mut recycled_ids);
-                                self.update_tile_and_board(tile, removed_tile.row, removed_tile.col);
-
-                                move_occurred = true;
-                            } else {
-                                self.update_tile_and_board(tile, row, col + (i - 1));
-
-                                if i > 1 {
-                                    move_occurred = true;
-                                }
-                            }
-                        }
-                    }
+        }
+
+        slots
+    }
+
+    /// Returns each value a new tile can take along with the probability of it being chosen.
+    pub fn spawn_probabilities(&self) -> Vec<(u32, f64)> {
+        let total: u32 = self.new_tile_params.tile_weights.iter().map(|&weight| weight as u32).sum();
+
+        self.new_tile_params.tile_choices.iter()
+            .zip(self.new_tile_params.tile_weights)
+            .map(|(&value, weight)| (value, weight as f64 / total as f64))
+            .collect()
+    }
+
+    /// Returns the value of the largest tile on the board, or 0 if the board is empty.
+    pub fn max_tile(&self) -> u32 {
+        self.get_tiles().iter().map(|tile| tile.value).max().unwrap_or(0)
+    }
+
+    /// Returns the rules this game is played with.
+    pub fn config(&self) -> GameConfig {
+        GameConfig {
+            board_size: BOARD_DIMENSION,
+            spawn_values: self.new_tile_params.tile_choices,
+            spawn_weights: self.new_tile_params.tile_weights,
+            goal_tile: self.merge_rule.goal_tile(),
+            difficulty: self.spawner().difficulty(),
+            topology: self.topology,
+            edges: self.edges,
+            merge_rule: self.merge_rule,
+        }
+    }
+
+    /// Returns the base 2 logarithm of every tile on the board, with 0 representing an empty slot.
+    /// See `ExponentGrid` for boards with other merge rules.
+    pub fn exponents(&self) -> ExponentGrid {
+        let mut exponents = [[0; BOARD_DIMENSION]; BOARD_DIMENSION];
+
+        for (row, slots) in self.board.iter().enumerate() {
+            for (col, slot) in slots.iter().enumerate() {
+                if let Some(tile) = slot {
+                    exponents[row][col] = self.merge_rule.rank(tile.value) as u8;
                 }
             }
-            _ => (),
         }
 
-        match move_occurred {
-            true => match self.get_random_free_slot() {
-                Some((i, j)) => {
-                    // New tile ID should not use the ID of a tile that was merged this turn.
-                    let new_id = self.get_id().unwrap();
-                    self.recycle_ids(recycled_ids);
+        exponents
+    }
 
-                    let new_tile_value = self.generate_tile_value();
-                    let (tile_background, tile_text) = self.get_tile_colors(new_tile_value);
+    /// Slides and merges tiles in the specified direction without spawning a new tile.
+    ///
+    /// Returns whether any tile moved, the IDs freed up by merges and whether the winning tile was
+    /// reached.
+    fn slide(&mut self, direction: Direction) -> (bool, Vec<usize>, bool) {
+        let mut move_occurred = false;
+        let mut recycled_ids: Vec<usize> = Vec::new();
+        self.reset_merged_flags();
 
-                    let new_tile = Tile::new(new_tile_value, new_id, tile_background, tile_text, i, j);
-                    self.board[i][j] = Some(new_tile);
+        let mut winning_tile_reached = false;
 
-                    // Game can only be won the first time a winning tile is reached.
-                    let mut send_game_won = false;
+        // Every line runs from the edge the tiles slide towards, so the tiles ahead of a tile have
+        // already been moved when it is its turn.
+        for line in self.topology.lines(direction) {
+            // A tile that wraps round can land in a slot that is yet to come, so a slot is only slid
+            // from if it still holds the tile it started with.
+            let starting_ids: Vec<Option<usize>> = line.iter()
+                .map(|&(row, col)| self.board[row][col].as_ref().map(|tile| tile.id))
+                .collect();
+
+            for (index, &(row, col)) in line.iter().enumerate() {
+                if self.board[row][col].as_ref().map(|tile| tile.id) != starting_ids[index] {
+                    continue
+                }
 
-                    if !self.game_won && winning_tile_reached {
-                        send_game_won = true;
-                        self.game_won = true;
+                let Some(mut tile) = self.board[row][col].take() else {
+                    continue
+                };
+
+                // The slots ahead of this tile and behind it, closest first. On wrapped lines the
+                // slots ahead run on round the line until they reach the tile's own start.
+                let (ahead, behind): (Vec<usize>, Vec<usize>) = match self.edges {
+                    Edges::Bounded => ((0..index).rev().collect(), (index + 1..line.len()).collect()),
+                    Edges::Wrapped => {
+                        let ring: Vec<usize> = (1..line.len()).map(|distance| (index + line.len() - distance) % line.len()).collect();
+                        let behind = ring.iter().rev().copied().collect();
+                        (ring, behind)
+                    },
+                };
+
+                // The closest slot ahead that holds a tile, if any.
+                let blocker = ahead.iter().position(|&slot| {
+                    let (ahead_row, ahead_col) = line[slot];
+                    self.board[ahead_row][ahead_col].is_some()
+                });
+                let blocker_slot = blocker.map(|position| ahead[position]);
+
+                // Rules that merge more than two tiles also take in the closest tiles behind this one.
+                let followers: Vec<usize> = behind.iter()
+                    .copied()
+                    .take_while(|&slot| Some(slot) != blocker_slot)
+                    .filter(|&slot| {
+                        let (behind_row, behind_col) = line[slot];
+                        self.board[behind_row][behind_col].is_some()
+                    })
+                    .take(self.merge_rule.tiles_per_merge() - 2)
+                    .collect();
+
+                // Double merges should not be allowed e.g. [2, 2, 2, 2] -> [0, 0, 4, 4] is a correct merge.
+                let merges = blocker_slot.is_some_and(|slot| {
+                    let (ahead_row, ahead_col) = line[slot];
+                    let ahead_tile = self.board[ahead_row][ahead_col].as_ref().unwrap();
+
+                    let mut values = vec![ahead_tile.value, tile.value];
+                    values.extend(followers.iter().map(|&behind| {
+                        let (behind_row, behind_col) = line[behind];
+                        self.board[behind_row][behind_col].as_ref().unwrap().value
+                    }));
+
+                    self.merge_rule.merge(&values).is_some() && ahead_tile.merged.is_empty()
+                });
+
+                if merges {
+                    let (ahead_row, ahead_col) = line[blocker_slot.unwrap()];
+                    let mut removed_tiles = vec![self.board[ahead_row][ahead_col].take().unwrap()];
+
+                    // The followers are merged in at the slot they slide to.
+                    for behind in followers {
+                        let (behind_row, behind_col) = line[behind];
+                        let mut follower = self.board[behind_row][behind_col].take().unwrap();
+                        (follower.row, follower.col) = (ahead_row, ahead_col);
+                        removed_tiles.push(follower);
                     }
 
-                    InputResult::Ok(new_id, self.get_tiles(), send_game_won)
-                },
-                None => unreachable!(),
+                    winning_tile_reached |= self.merge_tiles(&mut tile, removed_tiles, &mut recycled_ids);
+                    self.update_tile_and_board(tile, ahead_row, ahead_col);
+                    move_occurred = true;
+                } else {
+                    // The tile slides up to the slot behind the blocker. Without one, it slides up to
+                    // the edge of a bounded line and stays put on a wrapped one.
+                    let destination = match (blocker, self.edges) {
+                        (Some(position), _) => ahead[..position].last().copied().unwrap_or(index),
+                        (None, Edges::Bounded) => ahead.last().copied().unwrap_or(index),
+                        (None, Edges::Wrapped) => index,
+                    };
+                    let (new_row, new_col) = line[destination];
+                    self.update_tile_and_board(tile, new_row, new_col);
+
+                    if destination != index {
+                        move_occurred = true;
+                    }
+                }
             }
-                ,
-            false => InputResult::Err(InvalidMove),
         }
+
+        (move_occurred, recycled_ids, winning_tile_reached)
     }
 
-    /// Accepts two Tile references and performs necessary steps in merging them. This involves
-    /// storing the removed Tile in the resultant Tile's `merged` field and updating the Vec
-    /// of recycled IDs with the removed Tile's ID. 
+    /// Accepts a Tile reference and the Tiles merged into it and performs necessary steps in merging
+    /// them. This involves storing the removed Tiles in the resultant Tile's `merged` field and
+    /// updating the Vec of recycled IDs with the removed Tiles' IDs. The first removed Tile is the
+    /// one ahead of the resultant Tile, any others are behind it.
     ///
-    /// The resultant Tile needs to maintain a clone of the removed Tile so that the frontend has
-    /// access to the removed Tile's coordinates. This is necessary because the removed Tile needs
-    /// to be moved into its final position before being deleted for animation integrity.
+    /// The resultant Tile needs to maintain clones of the removed Tiles so that the frontend has
+    /// access to the removed Tiles' coordinates. This is necessary because the removed Tiles need
+    /// to be moved into their final position before being deleted for animation integrity.
     ///
-    /// The resultant Tile's value is doubled to reflect the merge and the score is incremented by 
-    /// this new value. Finally the resultant Tile's color is also updated to reflect its new value.
+    /// The resultant Tile's value is set by the game's merge rule, doubled unless the rule says
+    /// otherwise, and the score is incremented by this new value. Finally the resultant Tile's
+    /// color is also updated to reflect its new value.
     ///
-    /// If the resultant Tile's value is equal to self.WINNING_TILE, return true. Else return false.
-    fn merge_tiles(&mut self, merged_tile: &mut Tile, removed_tile: Tile, recycled_ids: &mut Vec<usize>) -> bool {
-        recycled_ids.push(removed_tile.id);
-        merged_tile.merged = Some(Box::new(removed_tile));
+    /// If the resultant Tile's value is the merge rule's goal tile, return true. Else return false.
+    fn merge_tiles(&mut self, merged_tile: &mut Tile, removed_tiles: Vec<Tile>, recycled_ids: &mut Vec<usize>) -> bool {
+        let mut values: Vec<u32> = removed_tiles.iter().map(|tile| tile.value).collect();
+        values.insert(1, merged_tile.value);
 
-        merged_tile.value *= 2;
+        merged_tile.value = self.merge_rule.merge(&values).expect("Only tiles that merge are merged.");
         self.score += merged_tile.value;
 
+        recycled_ids.extend(removed_tiles.iter().map(|tile| tile.id));
+        merged_tile.merged = removed_tiles;
+
         (merged_tile.background_color, merged_tile.text_color) = self.get_tile_colors(merged_tile.value);
 
-        if merged_tile.value == Game::WINNING_TILE {
-            true
-        } else {
-            false
-        }
+        merged_tile.value == self.merge_rule.goal_tile()
     }
 
     /// Receives a tile, the new row and col indexes, and updates both the tile's internal row and
@@ -507,6 +974,9 @@ impl Game {
     /// 1) 4 base colors are initialized in an array.
     /// 2) Every 4th power of 2 uses the next base color from the array.
     /// 3) All powers of 2 between multiples of 4 are interpolated between the two base colors.
+    ///
+    /// Under other merge rules, tiles are colored by their rank in the rule's sequence instead of
+    /// their power of 2.
     fn get_tile_colors(&self, tile_value: u32) -> (String, String) {
         let base_colors: [&str; 4] = [
                                       "#f2ba0d", // Yellow // Yellow // Yellow
@@ -518,7 +988,7 @@ impl Game {
         let num_interpolation_steps = 3;
 
         // Minus 1 is because tiles start at 2^1 rather than 2^0.
-        let log_2 = (log_2(tile_value) - 1) as usize;
+        let log_2 = self.merge_rule.rank(tile_value).saturating_sub(1) as usize;
         let base_color_index = (log_2 / num_interpolation_steps) % base_colors.len();
         let interpolation_offset = (log_2 % num_interpolation_steps) as f32;
 
@@ -562,6 +1032,21 @@ impl Game {
 
 // Helper functions
 
+/// Maps a coordinate through one of the 8 symmetries of the square board. Symmetries 0-3 are
+/// clockwise rotations by 0, 90, 180 and 270 degrees; 4-7 are the same rotations applied after a
+/// horizontal reflection.
+pub(crate) fn transform_coordinates(row: usize, col: usize, symmetry: usize) -> (usize, usize) {
+    let last = BOARD_DIMENSION - 1;
+    let (row, col) = if symmetry >= 4 { (row, last - col) } else { (row, col) };
+
+    match symmetry % 4 {
+        0 => (row, col),
+        1 => (col, last - row),
+        2 => (last - row, last - col),
+        _ => (last - col, row),
+    }
+}
+
 /// Computes log base 2 for a u32.
 fn log_2(mut num: u32) -> u32 {
     let mut log = 0;
@@ -574,6 +1059,19 @@ fn log_2(mut num: u32) -> u32 {
     log
 }
 
+/// Position of `value` in the Fibonacci sequence without its repeated 1: 1 for 1, 2 for 2, 3 for 3,
+/// 4 for 5 and so on. Returns `None` for numbers that are not Fibonacci numbers.
+fn fibonacci_rank(value: u32) -> Option<u32> {
+    let (mut current, mut next, mut rank) = (1u64, 2u64, 1);
+
+    while current < value as u64 {
+        (current, next) = (next, current + next);
+        rank += 1;
+    }
+
+    (current == value as u64).then_some(rank)
+}
+
 fn interpolate_hex_colors(color1: &HexColor, color2: &HexColor, t: f32) -> HexColor {
     let r = interpolate_component(color1.r, color2.r, t);
     let g = interpolate_component(color1.g, color2.g, t);
@@ -589,23 +1087,6 @@ fn interpolate_component(c1: u8, c2: u8, t: f32) -> String {
     format!("{:02X}", clamped_result)
 }
 
-trait CheckedAdd {
-    fn checked_add_max(self, rhs: usize, max: usize) -> Option<usize>;
-}
-
-/// Similar to the builtin `checked_add()` method but allows for defining a custom max
-impl CheckedAdd for usize {
-    fn checked_add_max(self, rhs: Self, max: Self) -> Option<Self> {
-        let sum = self + rhs;
-
-        if sum < max {
-            Some(sum)
-        } else {
-            None
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -626,7 +1107,7 @@ mod tests {
             const SAMPLE_SIZE: u32 = 10000;
 
             for _ in 0..SAMPLE_SIZE {
-                let tile = game.generate_tile_value();
+                let tile = game.generate_tile_value(&mut rand::thread_rng());
 
                 if tile == game.new_tile_params.tile_choices[NewTileParams::TWO] {
                     two_count += 1;
@@ -659,12 +1140,11 @@ mod tests {
         const NUM_STARTING_TILES: usize = 2;
 
         // Ensure that number of starting tiles is correct.
-        game.update_free_slots();
-        assert_eq!(game.free_slots.len(), NUM_TILES - NUM_STARTING_TILES);
+        assert_eq!(game.empty_slots().len(), NUM_TILES - NUM_STARTING_TILES);
 
         // Fill all empty slots with placeholders.
         for _ in NUM_STARTING_TILES..NUM_TILES {
-            let coord = game.get_random_free_slot();
+            let coord = game.get_random_free_slot(&mut rand::thread_rng());
 
             match coord {
                 Some((row, col)) => game.board[row][col] = 
@@ -674,8 +1154,7 @@ mod tests {
         }
 
         // Ensure that all board slots are filled.
-        game.update_free_slots();
-        assert_eq!(game.free_slots.len(), 0);
+        assert_eq!(game.empty_slots().len(), 0);
 
         // Brute force assurance that all board slots are filled.
         for row in 0..BOARD_DIMENSION {
@@ -687,7 +1166,7 @@ mod tests {
         }
 
         // Ensure that attempting to obtain a free slot when the board is full returns `None`.
-        assert_eq!(game.get_random_free_slot(), None);
+        assert_eq!(game.get_random_free_slot(&mut rand::thread_rng()), None);
     }
 
     #[test]
@@ -727,6 +1206,174 @@ mod tests {
         }
     }
 
+    #[test]
+    /// Two games created with the same seed should stay identical when given the same moves.
+    fn test_seeded_games_are_reproducible() {
+        let mut first = Game::with_seed(2048);
+        let mut second = Game::with_seed(2048);
+
+        assert!(first == second);
+
+        for direction in Direction::ALL.iter().cycle().take(200) {
+            first.make_move(*direction);
+            second.make_move(*direction);
+
+            assert!(first == second);
+        }
+
+        assert!(Game::with_seed(1).board != Game::with_seed(2).board || Game::with_seed(1).board != Game::with_seed(3).board);
+    }
+
+    #[test]
+    /// Hex boards slide along two more lines through every slot, so they can still move where a
+    /// square board is stuck.
+    fn test_hex_topology() {
+        assert_eq!(Topology::Square.lines(Direction::Up)[1], vec![(0, 1), (1, 1), (2, 1), (3, 1)]);
+        assert!(Topology::Square.lines(Direction::UpRight).is_empty());
+        assert_eq!(Topology::Hex.lines(Direction::UpRight).len(), 2 * BOARD_DIMENSION - 1);
+        assert_eq!(Topology::Hex.lines(Direction::DownLeft)[0], vec![(0, 0)]);
+
+        assert_eq!(Topology::Hex.direction_for_key("KeyE"), Some(Direction::UpRight));
+        assert_eq!(Topology::Square.direction_for_key("KeyE"), None);
+
+        for &direction in Topology::Hex.directions() {
+            assert_eq!(Direction::from_letter(direction.letter()), Some(direction));
+        }
+
+        let mut game = Game::from_notation("2,4,2,4/4,2,4,2/2,4,2,4/4,2,4,2").unwrap();
+        assert!(game.game_over());
+        assert!(game.afterstate(Direction::UpRight).is_none());
+
+        game.set_topology(Topology::Hex);
+        assert!(!game.game_over());
+        assert_eq!(game.config().topology, Topology::Hex);
+        assert!(matches!(game.receive_input("KeyA"), InputResult::Ok(..)));
+    }
+
+    #[test]
+    /// A move that reaches the winning tile should report the win even if other lines merge after
+    /// the winning one.
+    fn test_win_is_kept_through_later_merges() {
+        // The 2048 is made in the first line slid, before the 2s merge in a later one.
+        let mut game = Game::from_notation("1024,1024,0,0/2,2,0,0/0,0,0,0/0,0,0,0").unwrap();
+
+        assert!(matches!(game.make_move(Direction::Left), InputResult::Ok(_, _, true)));
+    }
+
+    #[test]
+    /// Under the Fibonacci rule, two tiles merge when they are consecutive Fibonacci numbers, and
+    /// games spawn 1s and 2s and are won at 2584.
+    fn test_fibonacci_merges() {
+        assert_eq!(MergeRule::Fibonacci.merge(&[1, 1]), Some(2));
+        assert_eq!(MergeRule::Fibonacci.merge(&[2, 1]), Some(3));
+        assert_eq!(MergeRule::Fibonacci.merge(&[3, 5]), Some(8));
+        assert_eq!(MergeRule::Fibonacci.merge(&[2, 2]), None);
+        assert_eq!(MergeRule::Fibonacci.merge(&[2, 5]), None);
+        assert_eq!(MergeRule::Fibonacci.merge(&[4, 4]), None);
+        assert_eq!(MergeRule::Doubling.merge(&[4, 4]), Some(8));
+        assert_eq!(MergeRule::Fibonacci.rank(2584), 17);
+        assert_eq!(MergeRule::Fibonacci.rank(4), 0);
+
+        let mut game = Game::with_merge_rule(Box::new(SeededSpawner::new(3)), MergeRule::Fibonacci);
+        assert!(game.get_tiles().iter().all(|tile| [1, 2].contains(&tile.value)));
+        assert_eq!(game.config().goal_tile, 2584);
+        assert_eq!(game.config().merge_rule, MergeRule::Fibonacci);
+
+        // Merges pair up from the edge as usual, each into the sum of the pair, and equal tiles
+        // other than 1s do not merge.
+        game.board = Game::empty().board;
+        for (col, value) in [1, 2, 3, 3].into_iter().enumerate() {
+            game.place_tile(0, col, value);
+        }
+
+        let (after, reward) = game.afterstate(Direction::Left).unwrap();
+        let row: Vec<u32> = (0..BOARD_DIMENSION).map(|col| after.board[0][col].as_ref().map_or(0, |tile| tile.value)).collect();
+        assert_eq!(row, vec![3, 3, 3, 0]);
+        assert_eq!(reward, 3);
+        assert_eq!(after.exponents()[0], [3, 3, 3, 0]);
+    }
+
+    #[test]
+    /// Under the tripling rule, three equal tiles in a line merge into one of three times their
+    /// value, and games spawn 3s and 9s.
+    fn test_triple_merges() {
+        assert_eq!(MergeRule::Tripling.merge(&[9, 9, 9]), Some(27));
+        assert_eq!(MergeRule::Tripling.merge(&[9, 9]), None);
+        assert_eq!(MergeRule::Tripling.merge(&[9, 9, 3]), None);
+        assert_eq!(MergeRule::Tripling.rank(2187), 7);
+
+        let mut game = Game::with_merge_rule(Box::new(SeededSpawner::new(5)), MergeRule::Tripling);
+        assert!(game.get_tiles().iter().all(|tile| [3, 9].contains(&tile.value)));
+        assert_eq!(game.config().goal_tile, 2187);
+
+        // Three equal tiles merge, two do not, and the tile left over after a merge slides on.
+        game.board = Game::empty().board;
+        for (row, values) in [[3, 3, 3, 3], [9, 0, 9, 0], [3, 0, 9, 9]].into_iter().enumerate() {
+            for (col, value) in values.into_iter().enumerate().filter(|&(_, value)| value > 0) {
+                game.place_tile(row, col, value);
+            }
+        }
+        let ids: Vec<usize> = (0..BOARD_DIMENSION).map(|col| game.board[0][col].as_ref().unwrap().id).collect();
+
+        let InputResult::Ok(_, _, _) = game.make_move(Direction::Left) else {
+            panic!("The tiles should have moved.");
+        };
+        assert_eq!(game.exponents()[..3], [[2, 1, 0, 0], [2, 2, 0, 0], [1, 2, 2, 0]][..]);
+        assert_eq!(game.score, 9);
+
+        // The merged tile keeps the tiles it took in, both moved to its slot.
+        let merged = game.board[0][0].as_ref().unwrap();
+        assert_eq!(merged.id, ids[1]);
+        assert_eq!(merged.merged.iter().map(|tile| (tile.id, tile.row, tile.col)).collect::<Vec<_>>(), vec![(ids[0], 0, 0), (ids[2], 0, 0)]);
+    }
+
+    #[test]
+    /// Tests that tiles on wrapped boards slide past the edge and back in from the opposite one
+    /// until they meet another tile.
+    fn test_wrapped_edges() {
+        let mut game = Game::from_notation("2,0,0,2/0,0,2,4/2,0,4,0/2,4,8,16").unwrap();
+        game.set_edges(Edges::Wrapped);
+        assert_eq!(game.config().edges, Edges::Wrapped);
+
+        // The 2 on the left slides past the left edge and merges into the 2 on the right. In the
+        // next two rows the first tile wraps round to stop behind the last one, which then wraps
+        // round to stop behind it in turn.
+        let (after, reward) = game.afterstate(Direction::Left).unwrap();
+        assert_eq!(after.exponents(), [[0, 0, 0, 2], [1, 2, 0, 0], [2, 0, 0, 1], [1, 2, 3, 4]]);
+        assert_eq!(reward, 4);
+
+        // Full lines without merges stay put.
+        let mut full = Game::from_notation("2,4,8,16/0,0,0,0/0,0,0,0/0,0,0,0").unwrap();
+        full.set_edges(Edges::Wrapped);
+        assert!(full.afterstate(Direction::Left).is_none());
+        assert!(full.afterstate(Direction::Right).is_none());
+
+        // Lines that are full of equal tiles merge the tile at the edge into the one at the far end.
+        let mut pairs = Game::from_notation("2,2,2,2/0,0,0,0/0,0,0,0/0,0,0,0").unwrap();
+        pairs.set_edges(Edges::Wrapped);
+        assert_eq!(pairs.afterstate(Direction::Left).unwrap().0.exponents()[0], [2, 0, 0, 2]);
+
+        // A lone tile has nothing ahead of it to stop it, so it stays put and the move is invalid.
+        let mut lone = Game::from_notation("0,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0").unwrap();
+        lone.set_edges(Edges::Wrapped);
+        assert!(lone.afterstate(Direction::Left).is_none());
+        assert!(lone.afterstate(Direction::Right).is_none());
+    }
+
+    #[test]
+    /// Tests that a new wrapped game can be played exactly when its two tiles share a row or a
+    /// column, since tiles that are alone on their lines cannot move.
+    fn test_new_wrapped_games_move_when_tiles_share_a_line() {
+        for seed in 0..1000 {
+            let mut game = Game::with_seed(seed);
+            game.set_edges(Edges::Wrapped);
+
+            let tiles = game.get_tiles();
+            let share_a_line = tiles[0].row == tiles[1].row || tiles[0].col == tiles[1].col;
+            assert_eq!(game.game_over(), !share_a_line, "seed {}", seed);
+        }
+    }
+
     #[test]
     /// Tests whether tiles are generating the correct colors.
     fn test_color_generator() {
@@ -1,8 +1,13 @@
 #![allow(non_camel_case_types)]
+use gloo::storage::{LocalStorage, Storage};
 use gloo::utils::document;
 use gloo_console::log;
 use lazy_static::lazy_static;
 use rust_2048::*;
+use rust_2048::challenge::{self, ChallengeResult};
+use rust_2048::notation::Position;
+use rust_2048::replay::{self, Replay};
+use rust_2048::spawn::Difficulty;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicU16, Ordering};
@@ -14,9 +19,18 @@ use wasm_bindgen_futures::spawn_local;
 use web_sys::{HtmlElement, window, CssAnimation, Element, Node, AddEventListenerOptions};
 use yew::prelude::*;
 mod counted_channel;
+mod ghost_view;
+mod hint;
+mod hot_seat_view;
+mod new_game_dialog;
+mod replay_viewer;
+mod variant_view;
+mod versus_view;
 
 const BORDER_SPACING: u16 = 4;
 const TILE_DIMENSION: u16 = 120;
+// Height of the flat-topped hexagonal tiles of hex boards, which are as wide as square tiles.
+const HEX_TILE_HEIGHT: u16 = 104;
 const COLORS: Colors = Colors::new();
 
 // Durations in milliseconds.
@@ -26,6 +40,9 @@ const DEFAULT_INIT_DURATION: u64 = 110;
 // const DEFAULT_SLIDE_DURATION: u64 = 1000;
 // const DEFAULT_EXPAND_DURATION: u64 = 1000;
 
+// Local storage key of the most recently finished game's replay.
+const REPLAY_STORAGE_KEY: &str = "rust-2048-replay";
+
 // Globally mutable variables. 
 lazy_static! {
     // Animation speeds adapt to the number of user inputs.
@@ -35,6 +52,15 @@ lazy_static! {
     // For storing touch coordinates whenever a touchstart event is registered.
     static ref X_DOWN: Mutex<Option<i32>> = Mutex::new(None);
     static ref Y_DOWN: Mutex<Option<i32>> = Mutex::new(None);
+
+    // Seed, starting position, difficulty and moves of the game in progress, from which it can be
+    // replayed or resumed. Games without a starting position begin on a new board.
+    static ref SESSION_SEED: Mutex<u64> = Mutex::new(challenge::random_seed());
+    static ref SESSION_START: Mutex<Option<Position>> = Mutex::new(None);
+    static ref SESSION_DIFFICULTY: Mutex<Difficulty> = Mutex::new(Difficulty::Normal);
+    static ref SESSION_MOVES: Mutex<Vec<Direction>> = Mutex::new(Vec::new());
+    // When each of the session's moves was made, as timestamps in milliseconds.
+    static ref SESSION_MOVE_TIMES: Mutex<Vec<f64>> = Mutex::new(Vec::new());
 }
 
 #[wasm_bindgen(module = "/prevent_arrow_scrolling.js")]
@@ -127,11 +153,10 @@ fn handle_game_over(game_won: bool) {
     }
 }
 
-fn remove_tile(id: usize) {
-    let document = gloo::utils::document();
+fn remove_tile(board: &Element, id: usize) {
     let id = convert_id_unicode(&id.to_string());
 
-    let removed_tile_node = document.query_selector(&id).unwrap().unwrap();
+    let removed_tile_node = board.query_selector(&id).unwrap().unwrap();
     let removed_tile_element = removed_tile_node.dyn_ref::<Element>().unwrap();
 
     removed_tile_element.remove();
@@ -143,14 +168,21 @@ fn update_score(new_score: u32) {
     score_node.set_inner_html(&new_score.to_string());
 }
 
-fn remove_tiles(removed_tile_ids: Vec<usize>) {
+fn remove_tiles(board: &Element, removed_tile_ids: Vec<usize>) {
     for id in removed_tile_ids {
-        remove_tile(id);
+        remove_tile(board, id);
     }
 }
 
-fn add_tile(game_tile: &rust_2048::Tile) {
-    let (top_offset, left_offset) = convert_to_pixels(game_tile.row, game_tile.col);
+/// Returns the container of the board on screen. Screens with more than one board pass each
+/// board's own container to the functions that draw tiles instead.
+fn board_container() -> Element {
+    let document = gloo::utils::document();
+    document.query_selector(".board-container").unwrap().unwrap()
+}
+
+fn add_tile(board: &Element, game_tile: &rust_2048::Tile) {
+    let (top_offset, left_offset) = slot_to_pixels(board, game_tile.row, game_tile.col);
 
     let font_size = compute_font_size(&game_tile.value.to_string());
     // let expand_init_animation = format!("expand-init {}ms ease-out;", CURRENT_EXPAND_DURATION.lock().unwrap());
@@ -175,8 +207,24 @@ fn add_tile(game_tile: &rust_2048::Tile) {
     html_tile.set_attribute("style", &style_args).unwrap();
     html_tile.set_id(&game_tile.id.to_string());
 
-    let board_container = document.query_selector(".board-container").unwrap().unwrap();
-    board_container.append_child(&html_tile).unwrap();
+    board.append_child(&html_tile).unwrap();
+}
+
+/// Replaces every tile on the board with the tiles of `game`.
+fn render_board(board: &Element, game: &Game) {
+    match board.query_selector_all("[class='tile cell']") {
+        Ok(node_list) => {
+            for i in 0..node_list.length() {
+                let node = node_list.get(i).unwrap();
+                node.dyn_ref::<HtmlElement>().unwrap().remove();
+            }
+        },
+        Err(_) => log!("NodeList could not be found."),
+    }
+
+    for tile in game.get_tiles() {
+        add_tile(board, tile);
+    }
 }
 
 /// Removes and re-appends html_tile to ensure animations trigger each time rather than only once.
@@ -232,17 +280,20 @@ fn expand_tile(html_tile: &HtmlElement) {
     re_append(html_tile);
 }
 
-fn slide_tile(html_tile: &HtmlElement, game_tile: &rust_2048::Tile, slide_duration: u64) {
+/// Slides a tile into the slot of `game_tile`. On boards with wrapped edges, `wrap_direction` is the
+/// direction of the move, and tiles that moved against it are slid off the edge ahead and back in
+/// from the opposite one.
+fn slide_tile(board: &Element, html_tile: &HtmlElement, game_tile: &rust_2048::Tile, slide_duration: u64, wrap_direction: Option<Direction>) {
     // Obtain current top and left offsets.
     let computed_style = window().unwrap().get_computed_style(&html_tile).unwrap().unwrap();
     let current_top_offset = computed_style.get_property_value("top").unwrap();
     let current_left_offset = computed_style.get_property_value("left").unwrap();
 
     // Compute new top and left offsets.
-    let (new_top_offset, new_left_offset) = convert_to_pixels(game_tile.row, game_tile.col);
+    let (new_top, new_left) = slot_to_pixels(board, game_tile.row, game_tile.col);
 
-    let new_top_offset = format!("{}px", new_top_offset);
-    let new_left_offset = format!("{}px", new_left_offset);
+    let new_top_offset = format!("{}px", new_top);
+    let new_left_offset = format!("{}px", new_left);
 
     html_tile.style().set_property("--current_top", &current_top_offset).unwrap();
     html_tile.style().set_property("--current_left", &current_left_offset).unwrap();
@@ -250,9 +301,34 @@ fn slide_tile(html_tile: &HtmlElement, game_tile: &rust_2048::Tile, slide_durati
     html_tile.style().set_property("--new_top", &new_top_offset).unwrap();
     html_tile.style().set_property("--new_left", &new_left_offset).unwrap();
 
-    let sliding_animation = format!("sliding {}ms ease-in forwards", slide_duration);
+    let mut sliding_animation = format!("sliding {}ms ease-in forwards", slide_duration);
 
-    if let Some(_) = &game_tile.merged {
+    if let Some(direction) = wrap_direction {
+        let (row_step, col_step) = direction.step();
+        let (origin_top, origin_left) = offboard_slot_to_pixels(board, 0, 0);
+        let (step_top, step_left) = offboard_slot_to_pixels(board, row_step, col_step);
+
+        // Tiles that moved against the direction of the move wrapped around.
+        let moved_top = new_top as f64 - parse_pixels(&current_top_offset);
+        let moved_left = new_left as f64 - parse_pixels(&current_left_offset);
+        let moved_ahead = moved_top * (step_top - origin_top) as f64 + moved_left * (step_left - origin_left) as f64;
+
+        if moved_ahead < 0.0 {
+            // The tile's line leaves the board one slot past the edge ahead and comes back in one
+            // slot past the edge behind.
+            let (exit_top, exit_left) = slot_past_edge_to_pixels(board, game_tile, (row_step, col_step));
+            let (entry_top, entry_left) = slot_past_edge_to_pixels(board, game_tile, (-row_step, -col_step));
+
+            html_tile.style().set_property("--exit_top", &format!("{}px", exit_top)).unwrap();
+            html_tile.style().set_property("--exit_left", &format!("{}px", exit_left)).unwrap();
+            html_tile.style().set_property("--entry_top", &format!("{}px", entry_top)).unwrap();
+            html_tile.style().set_property("--entry_left", &format!("{}px", entry_left)).unwrap();
+
+            sliding_animation = format!("wrapping {}ms linear forwards", slide_duration);
+        }
+    }
+
+    if !game_tile.merged.is_empty() {
         // Tiles with the --merged_value property set will be marked for the merging animation
         // later, along with having their value and colors updated as well.
         html_tile.style().set_property("--merged_value", &game_tile.value.to_string()).unwrap();
@@ -268,9 +344,7 @@ fn slide_tile(html_tile: &HtmlElement, game_tile: &rust_2048::Tile, slide_durati
 }
 
 /// Calls slide_tile() in a loop to move each tile into position. Returns the number of merged tiles.
-fn slide_tiles(node_list: web_sys::NodeList, tiles: &Vec<&rust_2048::Tile>) -> (Vec<usize>, u16) {
-    let document = gloo::utils::document();
-
+fn slide_tiles(board: &Element, node_list: web_sys::NodeList, tiles: &Vec<&rust_2048::Tile>, wrap_direction: Option<Direction>) -> (Vec<usize>, u16) {
     let mut removed_ids = Vec::new();
     let mut num_merged = 0;
 
@@ -282,69 +356,99 @@ fn slide_tiles(node_list: web_sys::NodeList, tiles: &Vec<&rust_2048::Tile>) -> (
         let tile_id = html_tile.get_attribute("id").unwrap().parse::<usize>().unwrap();
 
         if let Some(updated_tile) = get_tile_by_id(&tiles, tile_id) {
-            // If a tile is merged, its corresponding tile was removed from the backend.
-            // However, the backend provides a clone of the removed Tile in the `updated_tile.merged` field.
-            // This clone can be used to obtain the Tile's final position so the frontend can slide it 
-            // into that position before deleting it, thereby ensuring animation integrity.
-            // If the `merged` field is the `None` variant, that means that Tile was not merged.
-            if let Some(removed_tile) = &updated_tile.merged {
-                removed_ids.push(removed_tile.id);
+            // If a tile is merged, its corresponding tiles were removed from the backend.
+            // However, the backend provides clones of the removed Tiles in the `updated_tile.merged` field.
+            // These clones can be used to obtain the Tiles' final position so the frontend can slide them 
+            // into that position before deleting them, thereby ensuring animation integrity.
+            // If the `merged` field is empty, that means that Tile was not merged.
+            if !updated_tile.merged.is_empty() {
                 num_merged += 1;
+            }
 
-                let removed_html_node = document.query_selector(&convert_id_unicode(&removed_tile.id.to_string())).unwrap().unwrap();
+            for removed_tile in &updated_tile.merged {
+                removed_ids.push(removed_tile.id);
+
+                let removed_html_node = board.query_selector(&convert_id_unicode(&removed_tile.id.to_string())).unwrap().unwrap();
                 let removed_html_tile = removed_html_node.dyn_ref::<HtmlElement>().unwrap();
 
-                slide_tile(removed_html_tile, removed_tile, slide_duration);
+                slide_tile(board, removed_html_tile, removed_tile, slide_duration, wrap_direction);
 
                 // Mark this tile for removal from the frontend.
                 html_tile.style().set_property("--remove_id", &removed_tile.id.to_string()).unwrap();
             }
 
-            slide_tile(html_tile, updated_tile, slide_duration);
+            slide_tile(board, html_tile, updated_tile, slide_duration, wrap_direction);
         }
     }
 
     (removed_ids, num_merged)
 }
 
-async fn process_keydown_messages(game_state: Rc<RefCell<Game>>, mut keydown_rx: UnboundedReceiver<String>, mut animationend_rx: counted_channel::CountedReceiver, input_counter: Arc<AtomicU16>, input_handler: Arc<Closure<dyn FnMut(yew::Event)>>) {
+/// Slides the tiles on the board into the positions given by `tiles`, removes the tiles merged away
+/// and adds the newly spawned tile if there is one, waiting for every animation to finish.
+async fn animate_move(board: &Element, tiles: &Vec<&rust_2048::Tile>, new_tile_id: Option<usize>, animationend_rx: &mut counted_channel::CountedReceiver) {
+    animate_tiles(board, tiles, new_tile_id, None, animationend_rx).await
+}
+
+/// Same as `animate_move()` for boards with wrapped edges, on which the tiles that wrapped around
+/// during a move in `direction` slide off one edge and back in from the opposite one.
+async fn animate_wrapped_move(board: &Element, tiles: &Vec<&rust_2048::Tile>, new_tile_id: Option<usize>, direction: Direction, animationend_rx: &mut counted_channel::CountedReceiver) {
+    animate_tiles(board, tiles, new_tile_id, Some(direction), animationend_rx).await
+}
+
+async fn animate_tiles(board: &Element, tiles: &Vec<&rust_2048::Tile>, new_tile_id: Option<usize>, wrap_direction: Option<Direction>, animationend_rx: &mut counted_channel::CountedReceiver) {
+    match board.query_selector_all("[class='tile cell']") {
+        Ok(node_list) => {
+            let num_elements_slide = node_list.length() as u16;
+            let (removed_ids, num_merged) = slide_tiles(board, node_list, tiles, wrap_direction);
+
+            animationend_rx.recv_qty(num_elements_slide).await;
+
+            remove_tiles(board, removed_ids);
+            if let Some(new_tile_id) = new_tile_id {
+                add_tile(board, get_tile_by_id(tiles, new_tile_id).expect("Failed to find new Tile."));
+            }
+            animationend_rx.recv_qty(num_merged).await;
+        },
+        Err(_) => log!("NodeList could not be found."),
+    }
+}
+
+async fn process_keydown_messages(game_state: Rc<RefCell<Game>>, mut keydown_rx: UnboundedReceiver<String>, mut animationend_rx: counted_channel::CountedReceiver, input_counter: Arc<AtomicU16>, input_handler: Arc<Closure<dyn FnMut(yew::Event)>>, hints: Rc<hint::HintClient>, ghost: Option<Rc<ghost_view::GhostClient>>) {
     let game_state_mut = game_state.clone();
     let mut game_state_mut = game_state_mut.borrow_mut();
 
     while let Some(key_code) = keydown_rx.recv().await {
         match game_state_mut.receive_input(&key_code) {
             InputResult::Ok(new_tile_id, tiles, game_won) => {
-                let document = gloo::utils::document();
-                
-                match document.query_selector_all("[class='tile cell']") {
-                    Ok(node_list) => {
-                        // let mut now = instant::Instant::now();
-                        // log!(format!("{:?}", instant::Instant::now() - now));
-
-                        if input_counter.load(Ordering::SeqCst) == 1 {
-                            set_animation_duration(AnimationType::Sliding, false);
-                        }
-                        
-                        let num_elements_slide = node_list.length() as u16;
-                        let (removed_ids, num_merged) = slide_tiles(node_list, &tiles);
-
-                        if input_counter.load(Ordering::SeqCst) == 1 {
-                            set_animation_duration(AnimationType::Expanding, false);
-                        }
-
-                        animationend_rx.recv_qty(num_elements_slide).await;
+                // A hint requested for the previous board is stale now.
+                hints.cancel();
+                SESSION_MOVES.lock().unwrap().extend(Direction::from_key(&key_code));
+                SESSION_MOVE_TIMES.lock().unwrap().push(js_sys::Date::now());
+
+                // let mut now = instant::Instant::now();
+                // log!(format!("{:?}", instant::Instant::now() - now));
+
+                // Expand animations only start once sliding has finished, so both durations can be
+                // set up front.
+                if input_counter.load(Ordering::SeqCst) == 1 {
+                    set_animation_duration(AnimationType::Sliding, false);
+                    set_animation_duration(AnimationType::Expanding, false);
+                }
 
+                animate_move(&board_container(), &tiles, new_tile_id, &mut animationend_rx).await;
+                update_score(game_state_mut.score);
 
-                        remove_tiles(removed_ids);
-                        add_tile(get_tile_by_id(&tiles, new_tile_id).expect("Failed to find new Tile."));
-                        animationend_rx.recv_qty(num_merged).await;
-                        update_score(game_state_mut.score);
-                    },
-                    Err(_) => log!("NodeList could not be found."),
+                if let Some(ghost) = &ghost {
+                    ghost.player_moved(game_state_mut.score);
                 }
 
+                hints.set_board(game_state_mut.exponents());
+
                 if game_state_mut.game_over() || game_won {
                 // if true || game_won {
+                    let document = gloo::utils::document();
+
                     document.remove_event_listener_with_callback("keydown", Closure::as_ref(&input_handler).unchecked_ref()).unwrap();
                     document.remove_event_listener_with_callback("touchstart", Closure::as_ref(&input_handler).unchecked_ref()).unwrap();
                     document.remove_event_listener_with_callback("touchmove", Closure::as_ref(&input_handler).unchecked_ref()).unwrap();
@@ -356,6 +460,11 @@ async fn process_keydown_messages(game_state: Rc<RefCell<Game>>, mut keydown_rx:
                         }
                     }
 
+                    if game_state_mut.game_over() {
+                        save_session_replay();
+                        record_challenge_result(&game_state_mut);
+                    }
+
                     handle_game_over(game_won);
                     continue
                 }
@@ -429,7 +538,7 @@ fn interrupt_playback_rate(input_counter: Arc<AtomicU16>) {
                     let animation = CssAnimation::from(animation);
 
                     let animation_type = 
-                        if animation.animation_name() == "sliding" {
+                        if matches!(animation.animation_name().as_str(), "sliding" | "wrapping") {
                             AnimationType::Sliding
                         } else {
                             AnimationType::Expanding
@@ -463,6 +572,11 @@ fn interrupt_playback_rate(input_counter: Arc<AtomicU16>) {
 
 fn produce_input_handler(keydown_tx: UnboundedSender<String>, input_counter: Arc<AtomicU16>) -> Box<dyn FnMut(Event) -> ()> {
     Box::new(move |event: Event| {
+        // Typing a challenge code must not move any tiles.
+        if new_game_dialog::new_game_dialog_open() {
+            return
+        }
+
         let event_type = event.type_();
 
         let document = gloo::utils::document();
@@ -545,8 +659,9 @@ fn keep_playing_callback(input_handler: Arc<Closure<dyn FnMut(yew::Event)>>) ->
     })
 }
 
-fn new_game_callback(new_game_hook: UseStateHandle<u32>) -> Callback<MouseEvent> {
-    Callback::from(move |_| {
+/// Starts a new game with the seed passed to the callback.
+fn start_game_callback(new_game_hook: UseStateHandle<u32>) -> Callback<NewSession> {
+    Callback::from(move |session| {
         // Elements manipulated manually using web_sys do not get removed when this component is re-rendered.
         // Must remove them manually here.
 
@@ -565,13 +680,15 @@ fn new_game_callback(new_game_hook: UseStateHandle<u32>) -> Callback<MouseEvent>
         //     Err(_) => log!("Tiles could not be found."),
         // }
 
+        start_new_session(session);
         new_game_hook.set(*new_game_hook + 1);
     })
 }
 
 fn animationend_callback(animationend_tx: counted_channel::CountedSender) -> Closure<dyn FnMut(web_sys::AnimationEvent)> {
     Closure::wrap(Box::new(move |event: AnimationEvent| {
-        if event.animation_name() == "sliding" {
+        // Tiles that wrap around the edges of the board slide with the `wrapping` animation instead.
+        if matches!(event.animation_name().as_str(), "sliding" | "wrapping") {
             if event.type_() == "animationcancel" {
                 log!("canceled");
             }
@@ -595,6 +712,33 @@ fn animationend_callback(animationend_tx: counted_channel::CountedSender) -> Clo
 
 }
 
+/// Passes every key pressed on the page to `key_callback` and the end of every tile animation to
+/// `animationend_tx`, for screens that run their own game. Returns the function that removes the
+/// listeners again, for the cleanup of the screen's `use_effect`.
+fn add_game_listeners(key_callback: Option<Callback<String>>, animationend_tx: counted_channel::CountedSender) -> impl FnOnce() {
+    let document = document();
+    let body = gloo::utils::body();
+
+    let keydown_callback = key_callback.map(|key_callback| Closure::wrap(Box::new(move |event: KeyboardEvent| {
+        key_callback.emit(event.code());
+    }) as Box<dyn FnMut(KeyboardEvent)>));
+    let animationend_callback = animationend_callback(animationend_tx);
+
+    if let Some(keydown_callback) = &keydown_callback {
+        document.add_event_listener_with_callback("keydown", keydown_callback.as_ref().unchecked_ref()).unwrap();
+    }
+    body.add_event_listener_with_callback("animationend", animationend_callback.as_ref().unchecked_ref()).unwrap();
+    body.add_event_listener_with_callback("animationcancel", animationend_callback.as_ref().unchecked_ref()).unwrap();
+
+    move || {
+        if let Some(keydown_callback) = &keydown_callback {
+            document.remove_event_listener_with_callback("keydown", keydown_callback.as_ref().unchecked_ref()).unwrap();
+        }
+        body.remove_event_listener_with_callback("animationend", animationend_callback.as_ref().unchecked_ref()).unwrap();
+        body.remove_event_listener_with_callback("animationcancel", animationend_callback.as_ref().unchecked_ref()).unwrap();
+    }
+}
+
 fn transitionend_callback() -> Closure<dyn FnMut(web_sys::TransitionEvent)> {
     Closure::wrap(Box::new(move |event: TransitionEvent| {
         let event_target = event.target().unwrap();
@@ -629,13 +773,31 @@ fn decrement_counter(input_counter: Arc<AtomicU16>) {
     input_counter.fetch_sub(1, Ordering::SeqCst);
 }
 
+#[derive(Properties, PartialEq)]
+struct ContentProps {
+    view_replays_callback: Callback<MouseEvent>,
+    hot_seat_callback: Callback<MouseEvent>,
+    versus_callback: Callback<MouseEvent>,
+    variant_callback: Callback<variant_view::Variant>,
+}
+
 #[function_component(Content)]
-fn content() -> Html {
+fn content(props: &ContentProps) -> Html {
     // Prevents use of arrow keys for scrolling the page
     preventDefaultScrolling();
 
-    let game_state = Rc::new(RefCell::new(Game::new()));
+    // Resumes the game in progress when returning from the replay viewer.
+    let game_state = Rc::new(RefCell::new(session_game()));
     let game_state_for_move_processor = Rc::clone(&game_state);
+
+    // The AI worker is spawned once and reused across new games.
+    let hints = (*use_state(|| Rc::new(hint::HintClient::new()))).clone();
+    hints.set_board(game_state.borrow().exponents());
+    let hints_for_move_processor = Rc::clone(&hints);
+
+    // Races the best earlier game of the session's challenge, if there is one.
+    let ghost = ghost_view::session_ghost().map(|ghost| Rc::new(ghost_view::GhostClient::new(ghost)));
+    let ghost_for_move_processor = ghost.clone();
  
     // Attach a keydown event listener to the document.
     let (keydown_tx, keydown_rx) = mpsc::unbounded_channel();
@@ -648,7 +810,7 @@ fn content() -> Html {
     // Channel for animationend events to notify the keydown processor to process the next keystroke.
     let (animationend_tx, animationend_rx) = counted_channel::CountedChannel::new();
 
-    spawn_local(process_keydown_messages(game_state_for_move_processor, keydown_rx, animationend_rx, input_counter.clone(), input_handler_clone));
+    spawn_local(process_keydown_messages(game_state_for_move_processor, keydown_rx, animationend_rx, input_counter.clone(), input_handler_clone, hints_for_move_processor, ghost_for_move_processor));
 
     use_effect(move || {
         let document = gloo::utils::document();
@@ -669,21 +831,25 @@ fn content() -> Html {
         }
     });
 
-    use_effect(move || {
-        let body = gloo::utils::body();
+    // Keys are handled above along with touches, so only the animation listeners are shared with
+    // the other screens.
+    use_effect(move || add_game_listeners(None, animationend_tx));
 
-        let animationend_callback = animationend_callback(animationend_tx);
+    {
+        let ghost = ghost.clone();
 
-        body.add_event_listener_with_callback("animationend", animationend_callback.as_ref().unchecked_ref()).unwrap();
-        body.add_event_listener_with_callback("animationcancel", animationend_callback.as_ref().unchecked_ref()).unwrap();
+        use_effect(move || {
+            if let Some(ghost) = &ghost {
+                ghost.show();
+            }
 
-        || {
-            let body = gloo::utils::body();
-            body.remove_event_listener_with_callback("animationend", animationend_callback.as_ref().unchecked_ref()).unwrap();
-            body.remove_event_listener_with_callback("animationcancel", animationend_callback.as_ref().unchecked_ref()).unwrap();
-            drop(animationend_callback)
-        }
-    });
+            move || {
+                if let Some(ghost) = ghost {
+                    ghost.close();
+                }
+            }
+        });
+    }
 
     // Set transitionend listener for when game over layer transitions from hidden to visible.
     // The transition property for the buttons must be overwritten to allow for their color to
@@ -705,13 +871,23 @@ fn content() -> Html {
     // use_state() hook is used to trigger a re-render whenever the `New Game` button is clicked.
     let new_game = use_state(|| 0);
     let new_game_render = *new_game.clone();
-    let new_game_callback = new_game_callback(new_game.clone());
+    let start_game_callback = start_game_callback(new_game.clone());
+    let new_game_callback = Callback::from(|_| new_game_dialog::open_new_game_dialog());
+    let (challenge_text, challenge_summary) = match SESSION_START.lock().unwrap().is_some() {
+        true => (String::from("Custom position"), String::new()),
+        false => (format!("Challenge {}", session_challenge_key()), new_game_dialog::challenge_summary(&session_challenge_key())),
+    };
+    let challenge_style = format!("--challenge_text: {}", COLORS.text_light);
     let keep_playing_callback = keep_playing_callback(keep_playing_clone);
-    let placeholder_callback = Callback::from(|_| {});
+    let hint_callback = Callback::from(move |_| hints.request());
+    let ghost_panel = match &ghost {
+        Some(ghost) => html! { <ghost_view::GhostPanel best_score={ghost.best_score()}/> },
+        None => html! {},
+    };
 
     html! {
         <div class="content noselect" key={new_game_render}>
-            <MetadataContainer score={0} onclick={&new_game_callback}/>
+            <MetadataContainer score={game_state.borrow().score} onclick={&new_game_callback} hint_onclick={&hint_callback} replays_onclick={&props.view_replays_callback}/>
             <div class="board-container">
                 <GameBoard/>
                 { 
@@ -735,11 +911,17 @@ fn content() -> Html {
                         }
                     })
                 }
-                // GameLostLayer does not use `keep_playing_callback` but not worth creating a
-                // separate props struct for this.
-                <GameWonLayer new_game_callback={&new_game_callback} keep_playing_callback={&keep_playing_callback}/>
-                <GameLostLayer new_game_callback={&new_game_callback} keep_playing_callback={&placeholder_callback}/>
+                // GameLostLayer does not use `keep_playing_callback` and GameWonLayer does not use
+                // `watch_replay_callback` but not worth creating separate props structs for this.
+                <GameWonLayer new_game_callback={&new_game_callback} keep_playing_callback={&keep_playing_callback} watch_replay_callback={&props.view_replays_callback}/>
+                <GameLostLayer new_game_callback={&new_game_callback} keep_playing_callback={&keep_playing_callback} watch_replay_callback={&props.view_replays_callback}/>
+                <new_game_dialog::NewGameDialog start_callback={start_game_callback} hot_seat_callback={&props.hot_seat_callback} versus_callback={&props.versus_callback} variant_callback={&props.variant_callback}/>
+            </div>
+            <div class="challenge" style={challenge_style}>
+                <span>{ challenge_text }</span>
+                <span class="challenge-results">{ challenge_summary }</span>
             </div>
+            { ghost_panel }
         </div>
     }
 }
@@ -747,6 +929,8 @@ fn content() -> Html {
 #[derive(Properties, PartialEq)]
 struct MetadataContainerProps {
     onclick: Callback<MouseEvent>,
+    hint_onclick: Callback<MouseEvent>,
+    replays_onclick: Callback<MouseEvent>,
     score: u32,
 }
 
@@ -755,6 +939,8 @@ fn metadata_container(props: &MetadataContainerProps) -> Html {
     html! {
         <div class="metadata-container">
             <Score score={props.score}/>
+            <HintButton onclick={props.hint_onclick.clone()}/>
+            <button class="metadata replays" onclick={props.replays_onclick.clone()} style={button_style_args()}>{ "Replays" }</button>
             <NewGameButton onclick={props.onclick.clone()} button_text={"New Game"} disabled={false}/>
         </div>
     }
@@ -789,27 +975,48 @@ struct NewGameProps {
 
 #[function_component(NewGameButton)]
 fn new_game_button(props: &NewGameProps) -> Html {
-    let style_args = format!("--button_border: {};
-                              --button_background: {};
-                              --button_text: {};
-                              --button_hover: {};
-                              --hover_transition_duration: {}s",
-                              COLORS.text_dark,
-                              COLORS.button,
-                              COLORS.text_dark,
-                              COLORS.button_hover,
-                              0.20,
-                              );
+    let style_args = button_style_args();
 
     html! {
         <button class="metadata" onclick={props.onclick.clone()} disabled={props.disabled} style={style_args}>{ &props.button_text }</button>
     }
 }
 
+#[derive(Properties, PartialEq)]
+struct HintButtonProps {
+    onclick: Callback<MouseEvent>,
+}
+
+/// Asks the AI for a move recommendation. The button's text is replaced with the recommendation
+/// once it arrives; see `hint::HintClient`.
+#[function_component(HintButton)]
+fn hint_button(props: &HintButtonProps) -> Html {
+    let style_args = button_style_args();
+
+    html! {
+        <button class="metadata hint" onclick={props.onclick.clone()} style={style_args}>{ "Hint" }</button>
+    }
+}
+
+fn button_style_args() -> String {
+    format!("--button_border: {};
+             --button_background: {};
+             --button_text: {};
+             --button_hover: {};
+             --hover_transition_duration: {}s",
+             COLORS.text_dark,
+             COLORS.button,
+             COLORS.text_dark,
+             COLORS.button_hover,
+             0.20,
+             )
+}
+
 #[derive(Properties, PartialEq)]
 struct GameOverProps {
     new_game_callback: Callback<MouseEvent>,
     keep_playing_callback: Callback<MouseEvent>,
+    watch_replay_callback: Callback<MouseEvent>,
 }
 
 #[function_component(GameWonLayer)]
@@ -835,6 +1042,7 @@ fn game_lost_layer(props: &GameOverProps) -> Html {
         <div hidden=true class="gameover lost" style={style_args}>
             <div class="text">{"DEFEAT"}</div>
             <div class="buttons">
+                <NewGameButton onclick={props.watch_replay_callback.clone()} button_text={"Watch Replay"} disabled={true}/>
                 <NewGameButton onclick={props.new_game_callback.clone()} button_text={"Start Over"} disabled={true}/>
             </div>
         </div>
@@ -898,14 +1106,56 @@ fn footer() -> Html {
     }
 }
 
+/// Screen shown between the header and the footer.
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Game,
+    Replays,
+    HotSeat,
+    Versus,
+    Variant(variant_view::Variant),
+}
+
 #[function_component(App)]
 fn app() -> Html {
     set_background_colors();
 
+    // use_state() hook is used to switch between playing, watching replays and two-player games.
+    // They use the same global selectors for their tiles, so only one of them is ever mounted.
+    let view = use_state(|| View::Game);
+
+    let show = |target: View| {
+        let view = view.clone();
+        Callback::from(move |_| view.set(target))
+    };
+
+    let show_variant = {
+        let view = view.clone();
+        Callback::from(move |variant| view.set(View::Variant(variant)))
+    };
+
     html! {
         <>
             <Header/>
-            <Content/>
+            {
+                match *view {
+                    View::Game => html! {
+                        <Content view_replays_callback={show(View::Replays)} hot_seat_callback={show(View::HotSeat)} versus_callback={show(View::Versus)} variant_callback={show_variant}/>
+                    },
+                    View::Replays => html! {
+                        <replay_viewer::ReplayViewer close_callback={show(View::Game)}/>
+                    },
+                    View::HotSeat => html! {
+                        <hot_seat_view::HotSeat close_callback={show(View::Game)}/>
+                    },
+                    View::Versus => html! {
+                        <versus_view::Versus close_callback={show(View::Game)}/>
+                    },
+                    View::Variant(variant) => html! {
+                        <variant_view::VariantGame variant={variant} close_callback={show(View::Game)}/>
+                    },
+                }
+            }
             <Footer/>
         </>
     }
@@ -928,6 +1178,59 @@ fn convert_to_pixels(i: usize, j: usize) -> (u16, u16) {
     (top_offset, left_offset)
 }
 
+/// Same as `convert_to_pixels()` for the hexagonal cells of a hex board, in which every column sits
+/// half a cell lower than the one to its left.
+fn convert_hex_to_pixels(i: usize, j: usize) -> (u16, u16) {
+    let i = i as u16;
+    let j = j as u16;
+
+    let row_pitch = HEX_TILE_HEIGHT + BORDER_SPACING;
+    let top_offset = BORDER_SPACING + (row_pitch * i) + (row_pitch * j / 2);
+    let left_offset = BORDER_SPACING + ((TILE_DIMENSION * 3 / 4 + BORDER_SPACING) * j);
+
+    (top_offset, left_offset)
+}
+
+/// Pixel offsets of a slot on `board`, which has hexagonal cells if it has the `hex` class.
+fn slot_to_pixels(board: &Element, i: usize, j: usize) -> (u16, u16) {
+    match board.class_list().contains("hex") {
+        true => convert_hex_to_pixels(i, j),
+        false => convert_to_pixels(i, j),
+    }
+}
+
+/// Same as `slot_to_pixels()` for slots that may lie off the board, which tiles wrapping around the
+/// edges of the board pass through.
+fn offboard_slot_to_pixels(board: &Element, i: isize, j: isize) -> (i32, i32) {
+    let (origin_top, origin_left) = slot_to_pixels(board, 0, 0);
+    let (down_top, down_left) = slot_to_pixels(board, 1, 0);
+    let (right_top, right_left) = slot_to_pixels(board, 0, 1);
+
+    let (i, j) = (i as i32, j as i32);
+    let top = origin_top as i32 + i * (down_top as i32 - origin_top as i32) + j * (right_top as i32 - origin_top as i32);
+    let left = origin_left as i32 + i * (down_left as i32 - origin_left as i32) + j * (right_left as i32 - origin_left as i32);
+
+    (top, left)
+}
+
+/// Pixel offsets of the first slot off the board from the slot of `game_tile` in steps of `step`.
+fn slot_past_edge_to_pixels(board: &Element, game_tile: &rust_2048::Tile, step: (isize, isize)) -> (i32, i32) {
+    let on_board = |i: isize, j: isize| (0..BOARD_DIMENSION as isize).contains(&i) && (0..BOARD_DIMENSION as isize).contains(&j);
+    let (mut i, mut j) = (game_tile.row as isize, game_tile.col as isize);
+
+    while on_board(i, j) {
+        i += step.0;
+        j += step.1;
+    }
+
+    offboard_slot_to_pixels(board, i, j)
+}
+
+/// Reads a computed style length such as `124px` as a number of pixels.
+fn parse_pixels(length: &str) -> f64 {
+    length.trim_end_matches("px").parse().unwrap_or(0.0)
+}
+
 /// Determines font-size based on number of digits to prevent overflow.
 fn compute_font_size(value: &String) -> String {
     let font_size;
@@ -950,6 +1253,12 @@ fn compute_font_size(value: &String) -> String {
     font_size.to_string()
 }
 
+/// Shows `text` in the status line matched by `selector`.
+fn set_status(selector: &str, text: &str) {
+    let status = document().query_selector(selector).unwrap().unwrap();
+    status.set_text_content(Some(text));
+}
+
 /// Accepts a Vec of Tile references and an ID and returns an Option Tile with the corresponding ID if it
 /// is found, otherwise returns None.
 fn get_tile_by_id<'a>(tiles: &Vec<&'a rust_2048::Tile>, id: usize) -> Option<&'a rust_2048::Tile> {
@@ -970,6 +1279,110 @@ fn set_background_colors() {
     body.style().set_property("background-image", &linear_gradient).unwrap();
 }
 
+/// Rebuilds the game in progress from the session's seed and moves. Starts a new session if that
+/// game is already over.
+fn session_game() -> Game {
+    let difficulty = *SESSION_DIFFICULTY.lock().unwrap();
+    let config = GameConfig { difficulty, ..GameConfig::default() };
+    let mut game = replay::initial_game(SESSION_START.lock().unwrap().as_ref(), *SESSION_SEED.lock().unwrap(), &config);
+
+    for &direction in SESSION_MOVES.lock().unwrap().iter() {
+        game.make_move(direction);
+    }
+
+    if game.game_over() {
+        let seed = challenge::random_seed();
+        start_new_session(NewSession { seed, start: None, difficulty });
+        game = replay::initial_game(None, seed, &config);
+    }
+
+    game
+}
+
+/// Challenge code of the session game, followed by its difficulty unless it is a normal game.
+/// Results are kept separately for every difficulty.
+fn session_challenge_key() -> String {
+    let code = challenge::encode(*SESSION_SEED.lock().unwrap());
+
+    match *SESSION_DIFFICULTY.lock().unwrap() {
+        Difficulty::Normal => code,
+        difficulty => format!("{} ({})", code, difficulty.name()),
+    }
+}
+
+/// Stores the result of the finished session game under its challenge code and shows the updated
+/// results. Games started from a pasted position are not challenges and are not recorded.
+fn record_challenge_result(game: &Game) {
+    if SESSION_START.lock().unwrap().is_some() {
+        return
+    }
+
+    let code = session_challenge_key();
+
+    new_game_dialog::record_challenge_result(&code, ChallengeResult {
+        score: game.score,
+        moves: SESSION_MOVES.lock().unwrap().len(),
+        max_tile: game.max_tile(),
+    });
+
+    let document = gloo::utils::document();
+    let challenge_results = document.query_selector(".challenge-results").unwrap().unwrap();
+    challenge_results.set_inner_html(&new_game_dialog::challenge_summary(&code));
+}
+
+/// Returns a replay of the game in progress, or `None` if no moves have been made yet.
+fn session_replay() -> Option<Replay> {
+    let moves = SESSION_MOVES.lock().unwrap();
+
+    if moves.is_empty() {
+        return None
+    }
+
+    let times = SESSION_MOVE_TIMES.lock().unwrap();
+    let first_time = times.first().copied().unwrap_or_default();
+
+    let replay = Replay::record_from(SESSION_START.lock().unwrap().clone(),
+                                     *SESSION_SEED.lock().unwrap(),
+                                     &GameConfig { difficulty: *SESSION_DIFFICULTY.lock().unwrap(), ..GameConfig::default() },
+                                     &moves);
+
+    Some(replay.with_times(times.iter().map(|time| (time - first_time) as u32).collect()))
+}
+
+/// Saves a replay of the game in progress to local storage, replacing the previously saved one.
+/// Challenge games are also kept as the challenge's best game if they beat it, for racing later.
+fn save_session_replay() {
+    if let Some(replay) = session_replay() {
+        if SESSION_START.lock().unwrap().is_none() {
+            ghost_view::record_best_replay(&session_challenge_key(), replay.clone());
+        }
+
+        if LocalStorage::set(REPLAY_STORAGE_KEY, replay).is_err() {
+            log!("Replay could not be saved.");
+        }
+    }
+}
+
+/// How a new game is set up: the seed its spawns are drawn from, the difficulty they are chosen at
+/// and, for pasted positions, the position it starts from.
+#[derive(Clone, PartialEq)]
+pub struct NewSession {
+    pub seed: u64,
+    pub start: Option<Position>,
+    pub difficulty: Difficulty,
+}
+
+/// Saves the game in progress and starts recording a new one.
+fn start_new_session(session: NewSession) {
+    save_session_replay();
+
+    *SESSION_SEED.lock().unwrap() = session.seed;
+    *SESSION_START.lock().unwrap() = session.start;
+    *SESSION_DIFFICULTY.lock().unwrap() = session.difficulty;
+    SESSION_MOVES.lock().unwrap().clear();
+    SESSION_MOVE_TIMES.lock().unwrap().clear();
+}
+
 fn convert_id_unicode(id: &String) -> String {
     let mut converted_id = String::from("#\\3");
 
@@ -0,0 +1,292 @@
+//! Standard board features used by AI heuristics, analysis tools and the UI.
+//!
+//! All metrics work on base 2 exponents so that a merge changes a feature by the same amount
+//! regardless of how large the tiles involved are.
+
+use crate::{transform_coordinates, ExponentGrid, Game, BOARD_DIMENSION};
+
+/// Every metric computed for a single position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardMetrics {
+    pub empty_cells: usize,
+    /// 0 if every row is monotonic, otherwise negative. See `monotonicity()`.
+    pub row_monotonicity: i32,
+    /// 0 if every column is monotonic, otherwise negative. See `monotonicity()`.
+    pub col_monotonicity: i32,
+    /// 0 if all neighbouring tiles are equal, otherwise negative. See `smoothness()`.
+    pub smoothness: i32,
+    pub merge_opportunities: usize,
+    /// Exponent of the largest tile, or 0 for an empty board.
+    pub max_exponent: u8,
+    pub max_tile_position: Option<(usize, usize)>,
+    pub max_tile_in_corner: bool,
+    /// Between 0 and 1. See `snake_adherence()`.
+    pub snake_adherence: f64,
+}
+
+impl BoardMetrics {
+    pub fn from_game(game: &Game) -> Self {
+        BoardMetrics::from_exponents(&game.exponents())
+    }
+
+    pub fn from_exponents(exponents: &ExponentGrid) -> Self {
+        let max_tile_position = max_tile_position(exponents);
+
+        BoardMetrics {
+            empty_cells: empty_cells(exponents),
+            row_monotonicity: monotonicity(&rows(exponents)),
+            col_monotonicity: monotonicity(&cols(exponents)),
+            smoothness: smoothness(exponents),
+            merge_opportunities: merge_opportunities(exponents),
+            max_exponent: max_tile_position.map_or(0, |(row, col)| exponents[row][col]),
+            max_tile_position,
+            max_tile_in_corner: max_tile_position.is_some_and(is_corner),
+            snake_adherence: snake_adherence(exponents),
+        }
+    }
+}
+
+/// Number of empty slots on the board.
+pub fn empty_cells(exponents: &ExponentGrid) -> usize {
+    exponents.iter().flatten().filter(|&&exponent| exponent == 0).count()
+}
+
+/// Measures how consistently each line increases or decreases.
+///
+/// For every line, the exponent steps in the increasing and decreasing directions are summed
+/// separately, and the smaller of the two sums is the line's penalty. The result is the negated
+/// total penalty, so 0 means every line is monotonic in one direction or the other.
+pub fn monotonicity(lines: &[[u8; BOARD_DIMENSION]]) -> i32 {
+    let mut penalty = 0;
+
+    for line in lines {
+        let mut increasing = 0;
+        let mut decreasing = 0;
+
+        for pair in line.windows(2) {
+            let (current, next) = (pair[0] as i32, pair[1] as i32);
+
+            if next > current {
+                increasing += next - current;
+            } else {
+                decreasing += current - next;
+            }
+        }
+
+        penalty += increasing.min(decreasing);
+    }
+
+    -penalty
+}
+
+/// Negated sum of the exponent differences between horizontally and vertically adjacent tiles.
+/// Empty slots are skipped, so 0 means every tile has the same value as its occupied neighbours.
+pub fn smoothness(exponents: &ExponentGrid) -> i32 {
+    let mut penalty = 0;
+
+    for row in 0..BOARD_DIMENSION {
+        for col in 0..BOARD_DIMENSION {
+            let exponent = exponents[row][col] as i32;
+
+            if exponent == 0 {
+                continue
+            }
+
+            if col + 1 < BOARD_DIMENSION && exponents[row][col + 1] > 0 {
+                penalty += (exponent - exponents[row][col + 1] as i32).abs();
+            }
+
+            if row + 1 < BOARD_DIMENSION && exponents[row + 1][col] > 0 {
+                penalty += (exponent - exponents[row + 1][col] as i32).abs();
+            }
+        }
+    }
+
+    -penalty
+}
+
+/// Number of pairs of equal tiles that would meet if their row or column was slid, i.e. equal
+/// tiles that are adjacent once empty slots between them are removed.
+pub fn merge_opportunities(exponents: &ExponentGrid) -> usize {
+    let mut count = 0;
+
+    for line in rows(exponents).iter().chain(cols(exponents).iter()) {
+        let occupied: Vec<u8> = line.iter().copied().filter(|&exponent| exponent > 0).collect();
+        count += occupied.windows(2).filter(|pair| pair[0] == pair[1]).count();
+    }
+
+    count
+}
+
+/// Returns the position of the largest tile, preferring a corner if the largest value appears more
+/// than once. Returns `None` for an empty board.
+pub fn max_tile_position(exponents: &ExponentGrid) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+
+    for row in 0..BOARD_DIMENSION {
+        for col in 0..BOARD_DIMENSION {
+            if exponents[row][col] == 0 {
+                continue
+            }
+
+            let is_better = match best {
+                None => true,
+                Some((best_row, best_col)) => {
+                    let best_exponent = exponents[best_row][best_col];
+
+                    exponents[row][col] > best_exponent
+                        || (exponents[row][col] == best_exponent && is_corner((row, col)) && !is_corner((best_row, best_col)))
+                },
+            };
+
+            if is_better {
+                best = Some((row, col));
+            }
+        }
+    }
+
+    best
+}
+
+/// Measures how closely the board follows a "snake": tiles decreasing along a path that starts in
+/// a corner and zigzags back and forth across the board.
+///
+/// Returns the fraction of consecutive steps along the path that do not increase, using whichever
+/// of the 8 corner/orientation choices fits best. A perfect snake, or an empty board, scores 1.
+pub fn snake_adherence(exponents: &ExponentGrid) -> f64 {
+    let path = snake_path();
+    let num_steps = path.len() - 1;
+    let mut best = 0;
+
+    for symmetry in 0..8 {
+        let values: Vec<u8> = path.iter()
+            .map(|&(row, col)| {
+                let (row, col) = transform_coordinates(row, col, symmetry);
+                exponents[row][col]
+            })
+            .collect();
+
+        let in_order = values.windows(2).filter(|pair| pair[0] >= pair[1]).count();
+        best = best.max(in_order);
+    }
+
+    best as f64 / num_steps as f64
+}
+
+// Helper functions
+
+fn rows(exponents: &ExponentGrid) -> Vec<[u8; BOARD_DIMENSION]> {
+    exponents.to_vec()
+}
+
+fn cols(exponents: &ExponentGrid) -> Vec<[u8; BOARD_DIMENSION]> {
+    (0..BOARD_DIMENSION).map(|col| std::array::from_fn(|row| exponents[row][col])).collect()
+}
+
+fn is_corner((row, col): (usize, usize)) -> bool {
+    let last = BOARD_DIMENSION - 1;
+    (row == 0 || row == last) && (col == 0 || col == last)
+}
+
+/// Row-major path from the top-left corner that reverses direction on every row.
+fn snake_path() -> Vec<(usize, usize)> {
+    let mut path = Vec::with_capacity(BOARD_DIMENSION * BOARD_DIMENSION);
+
+    for row in 0..BOARD_DIMENSION {
+        for col in 0..BOARD_DIMENSION {
+            let col = if row % 2 == 0 { col } else { BOARD_DIMENSION - 1 - col };
+            path.push((row, col));
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// A perfect snake in the bottom-left corner should be monotonic, fully adherent and have its
+    /// maximum tile in the corner.
+    fn test_perfect_snake() {
+        let board: ExponentGrid = [
+            [1, 2, 3, 4],
+            [8, 7, 6, 5],
+            [9, 10, 11, 12],
+            [16, 15, 14, 13],
+        ];
+        let metrics = BoardMetrics::from_exponents(&board);
+
+        assert_eq!(metrics.empty_cells, 0);
+        // Rows alternate direction, so each row is monotonic on its own.
+        assert_eq!(metrics.row_monotonicity, 0);
+        assert_eq!(metrics.col_monotonicity, 0);
+        assert_eq!(metrics.snake_adherence, 1.0);
+        assert_eq!(metrics.max_exponent, 16);
+        assert_eq!(metrics.max_tile_position, Some((3, 0)));
+        assert!(metrics.max_tile_in_corner);
+        assert_eq!(metrics.merge_opportunities, 0);
+    }
+
+    #[test]
+    /// Hand-computed values for a small, irregular board built through `Game`.
+    fn test_irregular_board() {
+        let board: ExponentGrid = [
+            [0, 1, 0, 1],
+            [2, 0, 3, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 4],
+        ];
+        let metrics = BoardMetrics::from_game(&Game::from_exponents(&board));
+
+        assert_eq!(metrics.empty_cells, 11);
+
+        // Row 0: 0→1 up 1, 1→0 down 1, 0→1 up 1 => min(2, 1) = 1.
+        // Row 1: 2→0 down 2, 0→3 up 3, 3→0 down 3 => min(3, 5) = 3.
+        assert_eq!(metrics.row_monotonicity, -4);
+
+        // Column 0: 0, 2, 0, 0 => min(2, 2) = 2. Column 1: 1, 0, 0, 0 => 0.
+        // Column 2: 0, 3, 0, 0 => min(3, 3) = 3. Column 3: 1, 0, 0, 4 => min(4, 1) = 1.
+        assert_eq!(metrics.col_monotonicity, -6);
+
+        // No two occupied tiles are adjacent.
+        assert_eq!(metrics.smoothness, 0);
+
+        // The two 2-tiles in row 0 meet once the row is slid.
+        assert_eq!(metrics.merge_opportunities, 1);
+        assert_eq!(metrics.max_tile_position, Some((3, 3)));
+        assert!(metrics.max_tile_in_corner);
+    }
+
+    #[test]
+    fn test_smoothness_and_merges() {
+        let board: ExponentGrid = [
+            [3, 3, 1, 0],
+            [3, 0, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 5],
+        ];
+
+        // |3-3| + |3-1| horizontally, |3-3| vertically.
+        assert_eq!(smoothness(&board), -2);
+        // Two 8-tiles in row 0 and two in column 0.
+        assert_eq!(merge_opportunities(&board), 2);
+        assert!(snake_adherence(&board) < 1.0);
+    }
+
+    #[test]
+    /// Ties for the largest tile should report a corner if one is available.
+    fn test_max_tile_prefers_corner() {
+        let board: ExponentGrid = [
+            [0, 0, 0, 0],
+            [0, 6, 0, 0],
+            [0, 0, 0, 0],
+            [0, 0, 0, 6],
+        ];
+
+        assert_eq!(max_tile_position(&board), Some((3, 3)));
+        assert_eq!(max_tile_position(&[[0; BOARD_DIMENSION]; BOARD_DIMENSION]), None);
+        assert_eq!(snake_adherence(&[[0; BOARD_DIMENSION]; BOARD_DIMENSION]), 1.0);
+    }
+}
@@ -0,0 +1,170 @@
+use gloo::storage::{LocalStorage, Storage};
+use rust_2048::challenge::{self, ChallengeResult};
+use rust_2048::notation::Position;
+use rust_2048::spawn::Difficulty;
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::*;
+use crate::variant_view::Variant;
+
+// Local storage key of the results of every challenge played, keyed by challenge code.
+const CHALLENGE_RESULTS_STORAGE_KEY: &str = "rust-2048-challenge-results";
+
+#[derive(Properties, PartialEq)]
+pub struct NewGameDialogProps {
+    /// Starts a new game.
+    pub start_callback: Callback<NewSession>,
+    /// Leaves for a hot-seat game between two players.
+    pub hot_seat_callback: Callback<MouseEvent>,
+    /// Leaves for a split-screen race between two players.
+    pub versus_callback: Callback<MouseEvent>,
+    /// Leaves for a game under the rules of one of the variants.
+    pub variant_callback: Callback<Variant>,
+}
+
+/// Lets the player start a random game, today's daily challenge, the game behind a challenge code or
+/// a game from a pasted position, or switch to one of the games for two players or the variants.
+/// Shown and hidden through `open_new_game_dialog()` and `close_new_game_dialog()`.
+#[function_component(NewGameDialog)]
+pub fn new_game_dialog(props: &NewGameDialogProps) -> Html {
+    let (year, month, day) = local_date();
+
+    let random_callback = {
+        let start_callback = props.start_callback.clone();
+        Callback::from(move |_| start_callback.emit(new_session(challenge::random_seed(), None)))
+    };
+
+    let daily_callback = {
+        let start_callback = props.start_callback.clone();
+        Callback::from(move |_| start_callback.emit(new_session(challenge::daily_seed(year, month, day), None)))
+    };
+
+    let code_callback = {
+        let start_callback = props.start_callback.clone();
+
+        Callback::from(move |_| {
+            let document = gloo::utils::document();
+            let code_input = document.query_selector(".challenge-input").unwrap().unwrap();
+            let code = code_input.dyn_ref::<HtmlInputElement>().unwrap().value();
+
+            match challenge::decode(&code) {
+                Ok(seed) => start_callback.emit(new_session(seed, None)),
+                Err(error) => show_error(&format!("Invalid code: {}.", error)),
+            }
+        })
+    };
+
+    let position_callback = {
+        let start_callback = props.start_callback.clone();
+
+        Callback::from(move |_| {
+            let document = gloo::utils::document();
+            let position_input = document.query_selector(".position-input").unwrap().unwrap();
+            let notation = position_input.dyn_ref::<HtmlInputElement>().unwrap().value();
+
+            match notation.parse::<Position>() {
+                Ok(start) => start_callback.emit(new_session(challenge::random_seed(), Some(start))),
+                Err(error) => show_error(&format!("Invalid position: {}.", error)),
+            }
+        })
+    };
+
+    let cancel_callback = Callback::from(|_| close_new_game_dialog());
+
+    let style_args = format!("--dialog_background: {}{}; --dialog_text: {};",
+                             COLORS.text_light, COLORS.opacity,
+                             COLORS.text_dark,
+                             );
+
+    html! {
+        <div hidden=true class="new-game-dialog" style={style_args}>
+            <div class="text">{ "New Game" }</div>
+            <select class="difficulty-select">
+                { for Difficulty::ALL.iter().map(|&difficulty| html! {
+                    <option value={difficulty.name()} selected={difficulty == *SESSION_DIFFICULTY.lock().unwrap()}>{ difficulty.name() }</option>
+                })}
+            </select>
+            <NewGameButton onclick={random_callback} button_text={"Random"} disabled={false}/>
+            <NewGameButton onclick={daily_callback} button_text={format!("Daily {}-{:02}-{:02}", year, month, day)} disabled={false}/>
+            <input class="challenge-input" type="text" placeholder="Challenge code" maxlength="16"/>
+            <NewGameButton onclick={code_callback} button_text={"Play Code"} disabled={false}/>
+            <input class="position-input" type="text" placeholder="Paste a position, e.g. 2,4,0,0/0,0,0,0/0,0,0,0/0,0,0,2"/>
+            <NewGameButton onclick={position_callback} button_text={"Play Position"} disabled={false}/>
+            <div class="challenge-error"/>
+            <div class="dialog-buttons">
+                <NewGameButton onclick={props.hot_seat_callback.clone()} button_text={"Hot Seat"} disabled={false}/>
+                <NewGameButton onclick={props.versus_callback.clone()} button_text={"Versus"} disabled={false}/>
+                <NewGameButton onclick={cancel_callback} button_text={"Cancel"} disabled={false}/>
+            </div>
+            <div class="dialog-buttons">
+                { for Variant::ALL.iter().map(|&variant| html! {
+                    <NewGameButton onclick={props.variant_callback.reform(move |_| variant)} button_text={variant.name()} disabled={false}/>
+                })}
+            </div>
+        </div>
+    }
+}
+
+pub fn open_new_game_dialog() {
+    let document = gloo::utils::document();
+    let dialog = document.query_selector(".new-game-dialog").unwrap().unwrap();
+    dialog.remove_attribute("hidden").unwrap();
+}
+
+pub fn close_new_game_dialog() {
+    let document = gloo::utils::document();
+    let dialog = document.query_selector(".new-game-dialog").unwrap().unwrap();
+    dialog.dyn_ref::<HtmlElement>().unwrap().set_hidden(true);
+}
+
+/// Whether the dialog is showing, in which case keyboard and touch input should not move tiles.
+pub fn new_game_dialog_open() -> bool {
+    let document = gloo::utils::document();
+    document.query_selector(".new-game-dialog:not([hidden])").unwrap().is_some()
+}
+
+/// Stores the result of a finished game under its challenge code.
+pub fn record_challenge_result(code: &str, result: ChallengeResult) {
+    let mut results: HashMap<String, Vec<ChallengeResult>> = LocalStorage::get(CHALLENGE_RESULTS_STORAGE_KEY).unwrap_or_default();
+    results.entry(code.to_string()).or_default().push(result);
+
+    if LocalStorage::set(CHALLENGE_RESULTS_STORAGE_KEY, results).is_err() {
+        log!("Challenge result could not be saved.");
+    }
+}
+
+/// Describes the results stored for a challenge code.
+pub fn challenge_summary(code: &str) -> String {
+    let mut results: HashMap<String, Vec<ChallengeResult>> = LocalStorage::get(CHALLENGE_RESULTS_STORAGE_KEY).unwrap_or_default();
+    let results = results.remove(code).unwrap_or_default();
+
+    match results.iter().max_by_key(|result| result.score) {
+        Some(best) => format!("Best: {} in {} moves ({} played)", best.score, best.moves, results.len()),
+        None => String::from("Not played yet"),
+    }
+}
+
+/// A new session at the difficulty selected in the dialog.
+fn new_session(seed: u64, start: Option<Position>) -> NewSession {
+    let document = gloo::utils::document();
+    let select = document.query_selector(".difficulty-select").unwrap().unwrap();
+    let difficulty = Difficulty::from_name(&select.dyn_ref::<HtmlSelectElement>().unwrap().value()).unwrap_or_default();
+
+    NewSession { seed, start, difficulty }
+}
+
+/// Shows why the code or position entered could not be played.
+fn show_error(message: &str) {
+    let document = gloo::utils::document();
+    let error_text = document.query_selector(".challenge-error").unwrap().unwrap();
+    error_text.set_text_content(Some(message));
+}
+
+/// Returns today's year, month and day in the player's time zone.
+fn local_date() -> (i32, u32, u32) {
+    let date = js_sys::Date::new_0();
+    (date.get_full_year() as i32, date.get_month() + 1, date.get_date())
+}
@@ -0,0 +1,245 @@
+//! One-line text notation for positions, for setting up boards in tests and sharing them by paste.
+//!
+//! Rows are listed top to bottom and separated by `/`, with the tile values of each row separated
+//! by `,` and `0` for an empty slot. The board may be followed by `score=<points>` and
+//! `next=slide` or `next=spawn`, which default to a score of 0 and the player sliding next:
+//!
+//! ```text
+//! 2,4,0,8/0,0,0,0/0,0,16,0/0,0,0,2 score=48 next=slide
+//! ```
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::spawn::{RandomSpawner, SeededSpawner, SpawnProvider};
+use crate::{log_2, ExponentGrid, Game, BOARD_DIMENSION};
+
+/// Largest exponent that fits in a tile value.
+const MAX_EXPONENT: u32 = 31;
+
+/// Who acts next in a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    /// The player slides the tiles.
+    Slide,
+    /// A tile is about to spawn, as it does after every move.
+    Spawn,
+}
+
+/// A board with its score and the turn to act, as written in notation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub exponents: ExponentGrid,
+    pub score: u32,
+    pub next: Turn,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotationError {
+    /// The board does not have `BOARD_DIMENSION` rows.
+    Rows(usize),
+    /// A row does not have `BOARD_DIMENSION` tiles.
+    Columns { row: usize, count: usize },
+    /// A tile is not a number.
+    Tile(String),
+    /// A tile is not 0 or a power of two between 2 and 2^31.
+    Value(u64),
+    /// Metadata after the board is not `score=<points>` or `next=slide|spawn`.
+    Metadata(String),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotationError::Rows(count) => write!(f, "expected {} rows, found {}", BOARD_DIMENSION, count),
+            NotationError::Columns { row, count } => {
+                write!(f, "row {} has {} tiles instead of {}", row + 1, count, BOARD_DIMENSION)
+            },
+            NotationError::Tile(tile) => write!(f, "{:?} is not a tile value", tile),
+            NotationError::Value(value) => write!(f, "{} is not a valid tile value", value),
+            NotationError::Metadata(metadata) => write!(f, "unknown metadata {:?}", metadata),
+        }
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+impl Position {
+    /// Position of a game, which always waits for the player to slide.
+    pub fn of(game: &Game) -> Position {
+        Position {
+            exponents: game.exponents(),
+            score: game.score,
+            next: Turn::Slide,
+        }
+    }
+
+    /// Sets up a game in this position. Spawns, including one that is due right away, are drawn from
+    /// a generator seeded with `seed`, or from the thread-local generator if `seed` is `None`. A
+    /// board that already holds the winning tile does not report reaching it again.
+    pub fn to_game(&self, seed: Option<u64>) -> Game {
+        match seed {
+            Some(seed) => self.to_game_with(Box::new(SeededSpawner::new(seed))),
+            None => self.to_game_with(Box::new(RandomSpawner)),
+        }
+    }
+
+    /// Same as `to_game()`, with spawns chosen by `spawner`.
+    pub fn to_game_with(&self, spawner: Box<dyn SpawnProvider>) -> Game {
+        let mut game = Game::from_exponents(&self.exponents);
+        game.score = self.score;
+        game.game_won = game.max_tile() >= Game::WINNING_TILE;
+        game.set_spawner(spawner);
+
+        if self.next == Turn::Spawn {
+            game.spawn_tile();
+        }
+
+        game
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = self.exponents.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&exponent| if exponent == 0 { 0 } else { 1u32 << exponent }.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+
+        let next = match self.next {
+            Turn::Slide => "slide",
+            Turn::Spawn => "spawn",
+        };
+
+        write!(f, "{} score={} next={}", rows.join("/"), self.score, next)
+    }
+}
+
+impl FromStr for Position {
+    type Err = NotationError;
+
+    fn from_str(notation: &str) -> Result<Position, NotationError> {
+        let mut fields = notation.split_whitespace();
+        let board = fields.next().ok_or(NotationError::Rows(0))?;
+
+        let rows: Vec<&str> = board.split('/').collect();
+
+        if rows.len() != BOARD_DIMENSION {
+            return Err(NotationError::Rows(rows.len()))
+        }
+
+        let mut position = Position {
+            exponents: [[0; BOARD_DIMENSION]; BOARD_DIMENSION],
+            score: 0,
+            next: Turn::Slide,
+        };
+
+        for (row, tiles) in rows.iter().enumerate() {
+            let tiles: Vec<&str> = tiles.split(',').collect();
+
+            if tiles.len() != BOARD_DIMENSION {
+                return Err(NotationError::Columns { row, count: tiles.len() })
+            }
+
+            for (col, tile) in tiles.iter().enumerate() {
+                let value: u64 = tile.parse().map_err(|_| NotationError::Tile(tile.to_string()))?;
+
+                position.exponents[row][col] = match value {
+                    0 => 0,
+                    value if value.is_power_of_two() && (2..=1 << MAX_EXPONENT).contains(&value) => {
+                        log_2(value as u32) as u8
+                    },
+                    value => return Err(NotationError::Value(value)),
+                };
+            }
+        }
+
+        for field in fields {
+            match field.split_once('=') {
+                Some(("score", score)) => {
+                    position.score = score.parse().map_err(|_| NotationError::Metadata(field.to_string()))?;
+                },
+                Some(("next", "slide")) => position.next = Turn::Slide,
+                Some(("next", "spawn")) => position.next = Turn::Spawn,
+                _ => return Err(NotationError::Metadata(field.to_string())),
+            }
+        }
+
+        Ok(position)
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Position, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Game {
+    /// Sets up a game from notation, such as `2,4,0,8/0,0,0,0/0,0,16,0/0,0,0,2 score=48`. This is
+    /// the way to build specific boards for tests. If a spawn is due, it happens right away.
+    pub fn from_notation(notation: &str) -> Result<Game, NotationError> {
+        Ok(notation.parse::<Position>()?.to_game(None))
+    }
+
+    /// Writes the game's position in notation.
+    pub fn to_notation(&self) -> String {
+        Position::of(self).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    #[test]
+    fn test_notation_round_trip() {
+        let game = Game::from_notation("2,4,0,8/0,0,0,0/0,0,16,0/0,0,0,2147483648 score=48").unwrap();
+
+        assert_eq!(game.exponents(), [[1, 2, 0, 3], [0; 4], [0, 0, 4, 0], [0, 0, 0, 31]]);
+        assert_eq!(game.score, 48);
+        assert_eq!(game.to_notation(), "2,4,0,8/0,0,0,0/0,0,16,0/0,0,0,2147483648 score=48 next=slide");
+        assert!(Game::from_notation(&game.to_notation()).unwrap() == game);
+
+        let mut game = Game::from_notation("2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0").unwrap();
+        game.make_move(Direction::Left);
+        assert_eq!(game.score, 4);
+        assert!(game.to_notation().starts_with("4,"));
+    }
+
+    #[test]
+    fn test_pending_spawn() {
+        let position: Position = "2,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0 next=spawn".parse().unwrap();
+        assert_eq!(position.next, Turn::Spawn);
+
+        let game = position.to_game(Some(7));
+        assert_eq!(game.get_tiles().len(), 2);
+        assert!(game == position.to_game(Some(7)));
+    }
+
+    #[test]
+    fn test_invalid_notation() {
+        let parse = |notation: &str| notation.parse::<Position>().unwrap_err();
+
+        assert_eq!(parse(""), NotationError::Rows(0));
+        assert_eq!(parse("2,0,0,0/0,0,0,0/0,0,0,0"), NotationError::Rows(3));
+        assert_eq!(parse("2,0,0,0/0,0,0/0,0,0,0/0,0,0,0"), NotationError::Columns { row: 1, count: 3 });
+        assert_eq!(parse("2,x,0,0/0,0,0,0/0,0,0,0/0,0,0,0"), NotationError::Tile(String::from("x")));
+        assert_eq!(parse("2,6,0,0/0,0,0,0/0,0,0,0/0,0,0,0"), NotationError::Value(6));
+        assert_eq!(parse("1,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0"), NotationError::Value(1));
+        assert_eq!(parse("4294967296,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0"), NotationError::Value(1 << 32));
+        assert_eq!(parse("2,0,0,0/0,0,0,0/0,0,0,0/0,0,0,0 next=wait"), NotationError::Metadata(String::from("next=wait")));
+    }
+}
@@ -0,0 +1,344 @@
+//! N-tuple network value function trained by temporal-difference learning.
+//!
+//! Each tuple (a "pattern") is a fixed list of board coordinates. The exponents found at those
+//! coordinates index into a lookup table of weights, and the value of a board is the sum of the
+//! weights selected by every pattern. With symmetric sampling each pattern is also read under all
+//! 8 rotations/reflections of the board, sharing a single lookup table.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::ai::{self, Evaluator};
+use crate::{transform_coordinates, ExponentGrid, Game, BOARD_DIMENSION};
+
+/// A list of `(row, col)` coordinates read together as one feature.
+pub type Pattern = Vec<(usize, usize)>;
+
+/// Each cell is stored in 4 bits, so exponents above 15 (32768) share the same weights.
+const MAX_EXPONENT: u8 = 15;
+const CELL_STATES: usize = MAX_EXPONENT as usize + 1;
+
+const MAGIC: &[u8; 4] = b"NTUP";
+const FORMAT_VERSION: u32 = 1;
+
+pub struct NTupleNetwork {
+    patterns: Vec<Pattern>,
+    symmetric: bool,
+    /// For each pattern, every distinct orientation of it that is sampled from the board.
+    samples: Vec<Vec<Pattern>>,
+    weights: Vec<Vec<f32>>,
+}
+
+/// Summary of a single self-play training game.
+#[derive(Debug, Clone, Copy)]
+pub struct EpisodeResult {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: usize,
+}
+
+impl NTupleNetwork {
+    /// Creates a network with all weights set to zero.
+    ///
+    /// Panics if a pattern is empty, references a coordinate outside the board or is too long for
+    /// its lookup table to be indexed.
+    pub fn new(patterns: Vec<Pattern>, symmetric: bool) -> Self {
+        for pattern in &patterns {
+            assert!(!pattern.is_empty(), "Patterns must contain at least one cell.");
+            assert!(pattern.len() <= 6, "Patterns may contain at most 6 cells.");
+            assert!(pattern.iter().all(|&(row, col)| row < BOARD_DIMENSION && col < BOARD_DIMENSION),
+                    "Pattern coordinates must lie on the board.");
+        }
+
+        let samples = patterns.iter().map(|pattern| sample_orientations(pattern, symmetric)).collect();
+        let weights = patterns.iter().map(|pattern| vec![0.0; CELL_STATES.pow(pattern.len() as u32)]).collect();
+
+        NTupleNetwork {
+            patterns,
+            symmetric,
+            samples,
+            weights,
+        }
+    }
+
+    /// Two straight lines and three 2x2 squares of 4 cells each. Small enough (about 1.3MB of
+    /// weights) to train in minutes, and a reasonable default.
+    pub fn four_tuple_patterns() -> Vec<Pattern> {
+        vec![
+            vec![(0, 0), (0, 1), (0, 2), (0, 3)],
+            vec![(1, 0), (1, 1), (1, 2), (1, 3)],
+            vec![(0, 0), (0, 1), (1, 0), (1, 1)],
+            vec![(0, 1), (0, 2), (1, 1), (1, 2)],
+            vec![(1, 1), (1, 2), (2, 1), (2, 2)],
+        ]
+    }
+
+    /// The four 6-tuples popularized by Szubert and Jaśkowski. Much stronger, but each pattern
+    /// needs 16^6 weights (about 268MB in total).
+    pub fn six_tuple_patterns() -> Vec<Pattern> {
+        vec![
+            vec![(0, 0), (0, 1), (0, 2), (0, 3), (1, 0), (1, 1)],
+            vec![(1, 0), (1, 1), (1, 2), (1, 3), (2, 0), (2, 1)],
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)],
+            vec![(1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)],
+        ]
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    /// Total number of weights read when evaluating a board.
+    pub fn num_features(&self) -> usize {
+        self.samples.iter().map(|orientations| orientations.len()).sum()
+    }
+
+    /// Returns the estimated value (expected future score) of a board.
+    pub fn value(&self, exponents: &ExponentGrid) -> f32 {
+        let mut value = 0.0;
+
+        for (orientations, weights) in self.samples.iter().zip(&self.weights) {
+            for orientation in orientations {
+                value += weights[lookup_index(exponents, orientation)];
+            }
+        }
+
+        value
+    }
+
+    /// Adds `delta` to every weight that contributes to the value of the board.
+    pub fn update(&mut self, exponents: &ExponentGrid, delta: f32) {
+        for (orientations, weights) in self.samples.iter().zip(self.weights.iter_mut()) {
+            for orientation in orientations {
+                weights[lookup_index(exponents, orientation)] += delta;
+            }
+        }
+    }
+
+    /// Plays a single game against itself, updating the weights with TD(0) learning on
+    /// afterstates: after every move, the value of the previous afterstate is pulled towards the
+    /// points scored by the next move plus the value of the next afterstate.
+    ///
+    /// `learning_rate` is divided evenly among the features of the board.
+    pub fn train_episode(&mut self, learning_rate: f32) -> EpisodeResult {
+        let step_size = learning_rate / self.num_features() as f32;
+
+        let mut game = Game::new();
+        let mut previous_afterstate: Option<ExponentGrid> = None;
+        let mut moves = 0;
+
+        while let Some((_, afterstate, reward)) = ai::best_afterstate(&game, self) {
+            let exponents = afterstate.exponents();
+
+            if let Some(previous) = previous_afterstate {
+                let error = reward as f32 + self.value(&exponents) - self.value(&previous);
+                self.update(&previous, step_size * error);
+            }
+
+            previous_afterstate = Some(exponents);
+            game = afterstate;
+            game.spawn_tile();
+            moves += 1;
+        }
+
+        // No moves remain, so the final afterstate is worth nothing.
+        if let Some(previous) = previous_afterstate {
+            let error = -self.value(&previous);
+            self.update(&previous, step_size * error);
+        }
+
+        EpisodeResult {
+            score: game.score,
+            max_tile: game.max_tile(),
+            moves,
+        }
+    }
+
+    /// Writes the patterns and weights to `path` in a little-endian binary format.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&[self.symmetric as u8])?;
+        writer.write_all(&(self.patterns.len() as u32).to_le_bytes())?;
+
+        for pattern in &self.patterns {
+            writer.write_all(&[pattern.len() as u8])?;
+
+            for &(row, col) in pattern {
+                writer.write_all(&[row as u8, col as u8])?;
+            }
+        }
+
+        for weights in &self.weights {
+            for weight in weights {
+                writer.write_all(&weight.to_le_bytes())?;
+            }
+        }
+
+        writer.flush()
+    }
+
+    /// Reads a network previously written by `save()`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid_data("Not an n-tuple weights file."))
+        }
+
+        let version = read_u32(&mut reader)?;
+
+        if version != FORMAT_VERSION {
+            return Err(invalid_data(&format!("Unsupported weights file version {}.", version)))
+        }
+
+        let symmetric = read_u8(&mut reader)? != 0;
+        let num_patterns = read_u32(&mut reader)?;
+        let mut patterns = Vec::new();
+
+        for _ in 0..num_patterns {
+            let len = read_u8(&mut reader)? as usize;
+
+            if len == 0 || len > 6 {
+                return Err(invalid_data("Pattern length out of range."))
+            }
+
+            let mut pattern = Vec::with_capacity(len);
+
+            for _ in 0..len {
+                let row = read_u8(&mut reader)? as usize;
+                let col = read_u8(&mut reader)? as usize;
+
+                if row >= BOARD_DIMENSION || col >= BOARD_DIMENSION {
+                    return Err(invalid_data("Pattern coordinate lies outside the board."))
+                }
+
+                pattern.push((row, col));
+            }
+
+            patterns.push(pattern);
+        }
+
+        let mut network = NTupleNetwork::new(patterns, symmetric);
+        let mut buffer = [0; 4];
+
+        for weights in network.weights.iter_mut() {
+            for weight in weights.iter_mut() {
+                reader.read_exact(&mut buffer)?;
+                *weight = f32::from_le_bytes(buffer);
+            }
+        }
+
+        Ok(network)
+    }
+}
+
+impl Evaluator for NTupleNetwork {
+    fn evaluate(&self, game: &Game) -> f64 {
+        self.value(&game.exponents()) as f64
+    }
+
+    fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+}
+
+// Helper functions
+
+/// Returns the pattern itself, plus its 7 other rotations/reflections if `symmetric` is set.
+/// Orientations covering the exact same cells in the same order are only sampled once.
+fn sample_orientations(pattern: &Pattern, symmetric: bool) -> Vec<Pattern> {
+    let num_symmetries = if symmetric { 8 } else { 1 };
+    let mut orientations: Vec<Pattern> = Vec::with_capacity(num_symmetries);
+
+    for symmetry in 0..num_symmetries {
+        let orientation: Pattern = pattern.iter().map(|&(row, col)| transform_coordinates(row, col, symmetry)).collect();
+
+        if !orientations.contains(&orientation) {
+            orientations.push(orientation);
+        }
+    }
+
+    orientations
+}
+
+fn lookup_index(exponents: &ExponentGrid, pattern: &Pattern) -> usize {
+    pattern.iter().fold(0, |index, &(row, col)| {
+        index * CELL_STATES + exponents[row][col].min(MAX_EXPONENT) as usize
+    })
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buffer = [0; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buffer = [0; 4];
+    reader.read_exact(&mut buffer)?;
+    Ok(u32::from_le_bytes(buffer))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Every symmetric orientation of a board should be valued identically.
+    fn test_symmetric_value() {
+        let mut network = NTupleNetwork::new(NTupleNetwork::four_tuple_patterns(), true);
+        let board: ExponentGrid = [[1, 2, 3, 4], [0, 5, 0, 6], [7, 0, 0, 0], [0, 0, 1, 1]];
+
+        network.update(&board, 1.0);
+        network.update(&[[0, 0, 0, 0], [0, 3, 0, 0], [0, 0, 2, 0], [0, 0, 0, 9]], -0.5);
+
+        let value = network.value(&board);
+
+        for symmetry in 0..8 {
+            let mut transformed = [[0; BOARD_DIMENSION]; BOARD_DIMENSION];
+
+            for (row, exponents) in board.iter().enumerate() {
+                for (col, &exponent) in exponents.iter().enumerate() {
+                    let (new_row, new_col) = transform_coordinates(row, col, symmetry);
+                    transformed[new_row][new_col] = exponent;
+                }
+            }
+
+            assert_eq!(network.value(&transformed), value);
+        }
+    }
+
+    #[test]
+    /// Training should run to completion and a saved network should load back unchanged.
+    fn test_train_save_and_load() {
+        let mut network = NTupleNetwork::new(vec![vec![(0, 0), (0, 1), (0, 2)], vec![(1, 1), (2, 2)]], true);
+
+        for _ in 0..5 {
+            let result = network.train_episode(0.1);
+            assert!(result.moves > 0);
+        }
+
+        let path = std::env::temp_dir().join(format!("ntuple_test_{}.bin", std::process::id()));
+        network.save(&path).unwrap();
+        let loaded = NTupleNetwork::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(loaded.patterns() == network.patterns());
+        assert!(loaded.weights == network.weights);
+    }
+}
@@ -0,0 +1,193 @@
+//! Conversion to and from the `gameState` JSON that Gabriele Cirulli's original 2048 keeps in
+//! localStorage, so that games can be migrated between the two in either direction.
+//!
+//! The original stores its grid column by column: `cells[x][y]` is the tile in column `x` and row
+//! `y`, and every tile repeats its own position.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{Game, BOARD_DIMENSION};
+
+/// Largest tile value that can be imported, since tile values are stored as `u32`.
+const MAX_TILE_VALUE: u32 = 1 << 31;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginalGameState {
+    pub grid: OriginalGrid,
+    pub score: u32,
+    pub over: bool,
+    pub won: bool,
+    pub keep_playing: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OriginalGrid {
+    pub size: usize,
+    /// Indexed by column, then row.
+    pub cells: Vec<Vec<Option<OriginalTile>>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OriginalTile {
+    pub position: OriginalPosition,
+    pub value: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OriginalPosition {
+    /// Column.
+    pub x: usize,
+    /// Row.
+    pub y: usize,
+}
+
+#[derive(Debug)]
+pub enum StateError {
+    Json(serde_json::Error),
+    /// Only boards of `BOARD_DIMENSION` are supported.
+    GridSize(usize),
+    /// The cells do not form a `size` by `size` grid.
+    MalformedGrid,
+    /// A tile's position does not match the cell it is stored in.
+    Position { x: usize, y: usize, position: OriginalPosition },
+    NotPowerOfTwo(u32),
+    OutOfRange(u32),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Json(error) => write!(f, "invalid game state: {}", error),
+            StateError::GridSize(size) => write!(f, "unsupported grid size {}, expected {}", size, BOARD_DIMENSION),
+            StateError::MalformedGrid => write!(f, "grid cells do not match the grid size"),
+            StateError::Position { x, y, position } => {
+                write!(f, "tile in cell ({}, {}) claims to be at ({}, {})", x, y, position.x, position.y)
+            },
+            StateError::NotPowerOfTwo(value) => write!(f, "tile value {} is not a power of two", value),
+            StateError::OutOfRange(value) => write!(f, "tile value {} is out of range", value),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl OriginalGameState {
+    pub fn from_json(json: &str) -> Result<Self, StateError> {
+        serde_json::from_str(json).map_err(StateError::Json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Game states always serialize.")
+    }
+
+    /// Captures a game in the original's format. The original only keeps playing past the winning
+    /// tile when asked to, so a game that has been won is exported as having been continued.
+    pub fn from_game(game: &Game) -> Self {
+        let cells = (0..BOARD_DIMENSION)
+            .map(|x| {
+                (0..BOARD_DIMENSION)
+                    .map(|y| game.board[y][x].as_ref().map(|tile| OriginalTile {
+                        position: OriginalPosition { x, y },
+                        value: tile.value,
+                    }))
+                    .collect()
+            })
+            .collect();
+
+        OriginalGameState {
+            grid: OriginalGrid {
+                size: BOARD_DIMENSION,
+                cells,
+            },
+            score: game.score,
+            over: game.game_over(),
+            won: game.game_won,
+            keep_playing: game.game_won,
+        }
+    }
+
+    /// Builds a game from the saved state after validating every tile. A game that has been won
+    /// will not report reaching the winning tile again.
+    pub fn to_game(&self) -> Result<Game, StateError> {
+        let grid = &self.grid;
+
+        if grid.size != BOARD_DIMENSION {
+            return Err(StateError::GridSize(grid.size))
+        }
+
+        if grid.cells.len() != grid.size || grid.cells.iter().any(|column| column.len() != grid.size) {
+            return Err(StateError::MalformedGrid)
+        }
+
+        let mut game = Game::empty();
+
+        for (x, column) in grid.cells.iter().enumerate() {
+            for (y, cell) in column.iter().enumerate() {
+                let Some(tile) = cell else {
+                    continue
+                };
+
+                if tile.position != (OriginalPosition { x, y }) {
+                    return Err(StateError::Position { x, y, position: tile.position })
+                }
+
+                if tile.value < 2 || tile.value > MAX_TILE_VALUE {
+                    return Err(StateError::OutOfRange(tile.value))
+                }
+
+                if !tile.value.is_power_of_two() {
+                    return Err(StateError::NotPowerOfTwo(tile.value))
+                }
+
+                game.place_tile(y, x, tile.value);
+            }
+        }
+
+        game.score = self.score;
+        game.game_won = self.won;
+
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saved by the original game: a 2 in the top left corner, a 4 below it and an 8 in the top
+    /// right corner.
+    const SAVED_STATE: &str = r#"{"grid":{"size":4,"cells":[
+        [{"position":{"x":0,"y":0},"value":2},{"position":{"x":0,"y":1},"value":4},null,null],
+        [null,null,null,null],
+        [null,null,null,null],
+        [{"position":{"x":3,"y":0},"value":8},null,null,null]]},
+        "score":16,"over":false,"won":false,"keepPlaying":false}"#;
+
+    #[test]
+    fn test_import_and_export() {
+        let state = OriginalGameState::from_json(SAVED_STATE).unwrap();
+        let game = state.to_game().unwrap();
+
+        assert_eq!(game.exponents(), [[1, 0, 0, 3], [2, 0, 0, 0], [0; 4], [0; 4]]);
+        assert_eq!(game.score, 16);
+
+        let exported = OriginalGameState::from_game(&game);
+        assert_eq!(exported, state);
+        assert_eq!(OriginalGameState::from_json(&exported.to_json()).unwrap(), state);
+    }
+
+    #[test]
+    fn test_invalid_states() {
+        let with_value = |value: &str| SAVED_STATE.replace(r#""value":8"#, &format!(r#""value":{}"#, value));
+        let import = |json: &str| OriginalGameState::from_json(json).and_then(|state| state.to_game());
+
+        assert!(matches!(import(&with_value("12")), Err(StateError::NotPowerOfTwo(12))));
+        assert!(matches!(import(&with_value("1")), Err(StateError::OutOfRange(1))));
+        assert!(matches!(import(&with_value("4294967296")), Err(StateError::Json(_))));
+        assert!(matches!(import(&SAVED_STATE.replace(r#""x":3"#, r#""x":2"#)), Err(StateError::Position { x: 3, y: 0, .. })));
+        assert!(matches!(import(&SAVED_STATE.replace(r#""size":4"#, r#""size":5"#)), Err(StateError::GridSize(5))));
+        assert!(matches!(import(&SAVED_STATE.replace("null,null,null,null", "null,null,null")), Err(StateError::MalformedGrid)));
+    }
+}
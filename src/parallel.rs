@@ -0,0 +1,18 @@
+//! Maps work across CPU cores when the `parallel` feature is enabled, and sequentially otherwise.
+//!
+//! The web frontend is built without the feature, so wasm builds stay single-threaded and do not
+//! pull in rayon. Results are always returned in input order so that callers reducing them (e.g.
+//! summing floating point values) get identical answers with and without the feature.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(feature = "parallel")]
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+    items.par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+    items.iter().map(f).collect()
+}
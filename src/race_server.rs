@@ -0,0 +1,595 @@
+//! Multiplayer races over WebSocket: every player in a room plays their own board from a common
+//! seed, sends their moves to the server and is told how everyone stands after every move.
+//!
+//! The server is authoritative. It keeps every player's game, makes each move itself and rejects
+//! moves that are out of turn, from players who are out of moves or that do not move any tiles.
+//! The first player to reach the goal tile wins. If nobody does, the race ends once every player
+//! is out of moves or has left, and the highest score wins.
+//!
+//! Messages are JSON text frames: `ClientMessage` from the players and `ServerMessage` from the
+//! server. The rules and the protocol are always built; the server and client themselves need the
+//! `server` feature.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{Direction, Game, InputResult};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ClientMessage {
+    /// Takes a seat in the room that is filling up. Must be sent first.
+    Join { name: String },
+    /// Slides the tiles on the player's own board.
+    Move { direction: Direction },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ServerMessage {
+    /// Answers `Join` with the player's index and the number of players the room waits for.
+    Welcome { player: usize, players: usize },
+    /// Sent to everyone once the room is full. Every board is dealt from `seed`.
+    Start { seed: u64, names: Vec<String> },
+    /// Answers an accepted `Move` with the player's board after it, in notation, new tile included.
+    Moved { direction: Direction, board: String },
+    /// Answers a message that was not accepted.
+    Rejected { reason: String },
+    /// Sent to everyone after every accepted move and whenever a player leaves.
+    Standings { standings: Vec<Standing> },
+    /// Sent to everyone once when the race has been decided. `winner` is `None` for a tie.
+    Finished { winner: Option<usize>, standings: Vec<Standing> },
+}
+
+impl ClientMessage {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Client messages always serialize.")
+    }
+
+    pub fn from_json(json: &str) -> Result<ClientMessage, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+impl ServerMessage {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Server messages always serialize.")
+    }
+
+    pub fn from_json(json: &str) -> Result<ServerMessage, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// One player's place in a race.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Standing {
+    pub player: usize,
+    pub name: String,
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: usize,
+    /// Whether the player is out of moves or has left.
+    pub out: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RaceError {
+    /// The room already has all its players.
+    Full,
+    /// The room is still waiting for players.
+    NotStarted,
+    /// The race has already been decided.
+    Finished,
+    /// The player has no moves left or has left the room.
+    Out(usize),
+    /// The slide does not move any tiles.
+    InvalidMove(Direction),
+}
+
+impl fmt::Display for RaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaceError::Full => write!(f, "the room is full"),
+            RaceError::NotStarted => write!(f, "the race has not started yet"),
+            RaceError::Finished => write!(f, "the race is over"),
+            RaceError::Out(player) => write!(f, "player {} is out of the race", player),
+            RaceError::InvalidMove(direction) => write!(f, "sliding {:?} does not move any tiles", direction),
+        }
+    }
+}
+
+impl std::error::Error for RaceError {}
+
+struct Seat {
+    name: String,
+    game: Game,
+    moves: usize,
+    connected: bool,
+}
+
+impl Seat {
+    fn out(&self) -> bool {
+        !self.connected || self.game.game_over()
+    }
+}
+
+/// A race between a fixed number of players, which starts as soon as the last of them has joined.
+pub struct Room {
+    seed: u64,
+    capacity: usize,
+    seats: Vec<Seat>,
+    finished: bool,
+    winner: Option<usize>,
+}
+
+impl Room {
+    /// Opens a room for `capacity` players, who will all be dealt boards from `seed`.
+    pub fn new(seed: u64, capacity: usize) -> Room {
+        assert!(capacity > 0, "A race needs at least one player.");
+
+        Room {
+            seed,
+            capacity,
+            seats: Vec::new(),
+            finished: false,
+            winner: None,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn started(&self) -> bool {
+        self.seats.len() == self.capacity
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The player who won, or `None` while the race is on or if it ended in a tie.
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.seats.iter().map(|seat| seat.name.clone()).collect()
+    }
+
+    pub fn game(&self, player: usize) -> &Game {
+        &self.seats[player].game
+    }
+
+    /// Seats a new player and returns their index.
+    pub fn join(&mut self, name: &str) -> Result<usize, RaceError> {
+        if self.started() {
+            return Err(RaceError::Full)
+        }
+
+        self.seats.push(Seat {
+            name: name.to_string(),
+            game: Game::with_seed(self.seed),
+            moves: 0,
+            connected: true,
+        });
+
+        // Players who left before the start are out as soon as it starts.
+        self.update_result();
+        Ok(self.seats.len() - 1)
+    }
+
+    /// Makes a move on `player`'s board and returns the board after it.
+    pub fn make_move(&mut self, player: usize, direction: Direction) -> Result<&Game, RaceError> {
+        if !self.started() {
+            return Err(RaceError::NotStarted)
+        }
+
+        if self.finished {
+            return Err(RaceError::Finished)
+        }
+
+        if self.seats[player].out() {
+            return Err(RaceError::Out(player))
+        }
+
+        if let InputResult::Err(_) = self.seats[player].game.make_move(direction) {
+            return Err(RaceError::InvalidMove(direction))
+        }
+
+        self.seats[player].moves += 1;
+        self.update_result();
+        Ok(&self.seats[player].game)
+    }
+
+    /// Takes `player` out of the race, keeping their score.
+    pub fn leave(&mut self, player: usize) {
+        self.seats[player].connected = false;
+        self.update_result();
+    }
+
+    /// Every player's place, best first: the winner, then by score.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self.seats.iter().enumerate()
+            .map(|(player, seat)| Standing {
+                player,
+                name: seat.name.clone(),
+                score: seat.game.score,
+                max_tile: seat.game.max_tile(),
+                moves: seat.moves,
+                out: seat.out(),
+            })
+            .collect();
+
+        standings.sort_by_key(|standing| (Some(standing.player) != self.winner, std::cmp::Reverse(standing.score), standing.player));
+        standings
+    }
+
+    fn update_result(&mut self) {
+        if self.finished || !self.started() {
+            return
+        }
+
+        if let Some(player) = self.seats.iter().position(|seat| seat.game.max_tile() >= seat.game.config().goal_tile) {
+            self.finished = true;
+            self.winner = Some(player);
+        } else if self.seats.iter().all(Seat::out) {
+            let best = self.seats.iter().map(|seat| seat.game.score).max().unwrap_or(0);
+            let mut leaders = self.seats.iter().enumerate().filter(|(_, seat)| seat.game.score == best);
+
+            self.finished = true;
+            self.winner = match (leaders.next(), leaders.next()) {
+                (Some((player, _)), None) => Some(player),
+                _ => None,
+            };
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+pub use network::{play, serve, ClientError, RaceResult, ServerConfig};
+
+#[cfg(feature = "server")]
+mod network {
+    use std::fmt;
+    use std::io::{self, ErrorKind};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use tungstenite::handshake::HandshakeError;
+    use tungstenite::Message;
+
+    use super::{ClientMessage, RaceError, Room, ServerMessage, Standing};
+    use crate::strategy::Strategy;
+    use crate::{challenge, Direction, Game};
+
+    // How long a connection waits for a message from its player before passing on messages for
+    // them from the rest of the room.
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    pub struct ServerConfig {
+        /// Number of players every race waits for.
+        pub players: usize,
+        /// Seed of the first race, counted up for every race after it. Random seeds if `None`.
+        pub seed: Option<u64>,
+    }
+
+    /// A room along with a channel to each of its players' connections.
+    struct Table {
+        room: Room,
+        outboxes: Vec<Sender<ServerMessage>>,
+    }
+
+    impl Table {
+        fn join(&mut self, name: &str, outbox: Sender<ServerMessage>) -> Result<usize, RaceError> {
+            let player = self.room.join(name)?;
+            let _ = outbox.send(ServerMessage::Welcome { player, players: self.room.capacity() });
+            self.outboxes.push(outbox);
+
+            if self.room.started() {
+                self.broadcast(ServerMessage::Start { seed: self.room.seed(), names: self.room.names() });
+                self.report(false);
+            }
+
+            Ok(player)
+        }
+
+        fn make_move(&mut self, player: usize, direction: Direction) -> Result<(), RaceError> {
+            let board = self.room.make_move(player, direction)?.to_notation();
+            let _ = self.outboxes[player].send(ServerMessage::Moved { direction, board });
+            self.report(false);
+            Ok(())
+        }
+
+        fn leave(&mut self, player: usize) {
+            let finished = self.room.finished();
+            self.room.leave(player);
+
+            if self.room.started() {
+                self.report(finished);
+            }
+        }
+
+        /// Sends the standings, or the result if the race has just been decided.
+        fn report(&self, finished_before: bool) {
+            if finished_before {
+                return
+            }
+
+            let standings = self.room.standings();
+
+            self.broadcast(match self.room.finished() {
+                true => ServerMessage::Finished { winner: self.room.winner(), standings },
+                false => ServerMessage::Standings { standings },
+            });
+        }
+
+        fn broadcast(&self, message: ServerMessage) {
+            // Players who have left no longer receive anything.
+            for outbox in &self.outboxes {
+                let _ = outbox.send(message.clone());
+            }
+        }
+    }
+
+    /// Hands out seats in the room that is filling up, and opens a new one once it is full.
+    struct Lobby {
+        config: ServerConfig,
+        races: u64,
+        open: Arc<Mutex<Table>>,
+    }
+
+    impl Lobby {
+        fn new(config: ServerConfig) -> Lobby {
+            let open = Lobby::open_table(&config, 0);
+            Lobby { config, races: 0, open }
+        }
+
+        fn open_table(config: &ServerConfig, race: u64) -> Arc<Mutex<Table>> {
+            let seed = match config.seed {
+                Some(seed) => seed + race,
+                None => challenge::random_seed(),
+            };
+
+            Arc::new(Mutex::new(Table { room: Room::new(seed, config.players), outboxes: Vec::new() }))
+        }
+
+        fn join(&mut self, name: &str, outbox: Sender<ServerMessage>) -> (Arc<Mutex<Table>>, usize) {
+            if self.open.lock().unwrap().room.started() {
+                self.races += 1;
+                self.open = Lobby::open_table(&self.config, self.races);
+            }
+
+            let player = self.open.lock().unwrap().join(name, outbox).expect("Open rooms always have a free seat.");
+            (Arc::clone(&self.open), player)
+        }
+    }
+
+    /// Hosts races for every connection made to `listener`, each on its own thread, until accepting
+    /// connections fails.
+    pub fn serve(listener: TcpListener, config: ServerConfig) -> io::Result<()> {
+        let lobby = Arc::new(Mutex::new(Lobby::new(config)));
+
+        loop {
+            let (stream, _) = listener.accept()?;
+            let lobby = Arc::clone(&lobby);
+
+            thread::spawn(move || handle_connection(stream, lobby));
+        }
+    }
+
+    fn handle_connection(stream: TcpStream, lobby: Arc<Mutex<Lobby>>) {
+        // Every move waits for its answer, which small-packet batching would hold back.
+        if stream.set_nodelay(true).is_err() {
+            return
+        }
+
+        let Ok(mut socket) = tungstenite::accept(stream) else {
+            return
+        };
+
+        if socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)).is_err() {
+            return
+        }
+
+        let (outbox, inbox) = mpsc::channel::<ServerMessage>();
+        let mut seat: Option<(Arc<Mutex<Table>>, usize)> = None;
+
+        'connection: loop {
+            for message in inbox.try_iter() {
+                if socket.send(Message::text(message.to_json())).is_err() {
+                    break 'connection
+                }
+            }
+
+            let text = match socket.read() {
+                Ok(Message::Text(text)) => text,
+                // Close frames are answered by tungstenite, after which reading fails.
+                Ok(_) => continue,
+                Err(tungstenite::Error::Io(error)) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+                Err(_) => break,
+            };
+
+            let result = match (ClientMessage::from_json(&text), &seat) {
+                (Err(error), _) => Err(error.to_string()),
+                (Ok(ClientMessage::Join { .. }), Some(_)) => Err(String::from("already in a race")),
+                (Ok(ClientMessage::Join { name }), None) => {
+                    seat = Some(lobby.lock().unwrap().join(&name, outbox.clone()));
+                    Ok(())
+                },
+                (Ok(ClientMessage::Move { .. }), None) => Err(String::from("join a race first")),
+                (Ok(ClientMessage::Move { direction }), Some((table, player))) => {
+                    table.lock().unwrap().make_move(*player, direction).map_err(|error| error.to_string())
+                },
+            };
+
+            if let Err(reason) = result {
+                let _ = outbox.send(ServerMessage::Rejected { reason });
+            }
+        }
+
+        if let Some((table, player)) = seat {
+            table.lock().unwrap().leave(player);
+        }
+    }
+
+    #[derive(Debug)]
+    pub enum ClientError {
+        Socket(Box<tungstenite::Error>),
+        /// The server sent something that is not a `ServerMessage`.
+        Protocol(serde_json::Error),
+        /// The server's board differs from the one the client expected after a move.
+        Diverged { expected: String, actual: String },
+    }
+
+    impl fmt::Display for ClientError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ClientError::Socket(error) => write!(f, "connection failed: {}", error),
+                ClientError::Protocol(error) => write!(f, "unexpected message: {}", error),
+                ClientError::Diverged { expected, actual } => write!(f, "expected board {} but the server has {}", expected, actual),
+            }
+        }
+    }
+
+    impl std::error::Error for ClientError {}
+
+    impl From<tungstenite::Error> for ClientError {
+        fn from(error: tungstenite::Error) -> Self {
+            ClientError::Socket(Box::new(error))
+        }
+    }
+
+    /// How a race played by `play()` ended.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct RaceResult {
+        pub player: usize,
+        pub winner: Option<usize>,
+        pub standings: Vec<Standing>,
+    }
+
+    /// Joins a race on the server at `address` (`host:port`) as `name` and plays it to the end with
+    /// `strategy`. Every move is also made on a local copy of the board, which has to match the
+    /// server's.
+    pub fn play(address: &str, name: &str, strategy: &mut dyn Strategy) -> Result<RaceResult, ClientError> {
+        let stream = TcpStream::connect(address).map_err(tungstenite::Error::Io)?;
+        stream.set_nodelay(true).map_err(tungstenite::Error::Io)?;
+
+        let (mut socket, _) = tungstenite::client(format!("ws://{}/", address), stream).map_err(|error| match error {
+            HandshakeError::Failure(error) => error,
+            HandshakeError::Interrupted(_) => unreachable!("Blocking streams are never interrupted."),
+        })?;
+        socket.send(Message::text(ClientMessage::Join { name: name.to_string() }.to_json()))?;
+
+        let mut player = 0;
+        let mut game: Option<Game> = None;
+
+        loop {
+            let Message::Text(text) = socket.read()? else {
+                continue
+            };
+
+            match ServerMessage::from_json(&text).map_err(ClientError::Protocol)? {
+                ServerMessage::Welcome { player: seat, .. } => player = seat,
+                ServerMessage::Start { seed, .. } => game = Some(Game::with_seed(seed)),
+                ServerMessage::Moved { direction, board } => {
+                    let Some(game) = &mut game else {
+                        continue
+                    };
+
+                    game.make_move(direction);
+
+                    if game.to_notation() != board {
+                        return Err(ClientError::Diverged { expected: game.to_notation(), actual: board })
+                    }
+                },
+                // Moves sent just before the race was decided are rejected; the result follows.
+                ServerMessage::Rejected { .. } | ServerMessage::Standings { .. } => continue,
+                ServerMessage::Finished { winner, standings } => {
+                    let _ = socket.close(None);
+                    return Ok(RaceResult { player, winner, standings })
+                },
+            }
+
+            // The next move is sent once the last one has been answered.
+            if let Some(direction) = game.as_ref().and_then(|game| strategy.choose(game)) {
+                socket.send(Message::text(ClientMessage::Move { direction }.to_json()))?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_move(game: &Game) -> Direction {
+        Direction::ALL.into_iter().find(|&direction| game.afterstate(direction).is_some()).unwrap()
+    }
+
+    #[test]
+    fn test_race_starts_when_full() {
+        let mut room = Room::new(21, 2);
+        assert_eq!(room.join("alice"), Ok(0));
+        assert_eq!(room.make_move(0, Direction::Up).err(), Some(RaceError::NotStarted));
+        assert_eq!(room.join("bob"), Ok(1));
+        assert_eq!(room.join("carol"), Err(RaceError::Full));
+
+        // Both players are dealt the same board and see the same spawns.
+        assert!(room.game(0) == room.game(1));
+
+        let direction = first_move(room.game(0));
+        assert!(room.make_move(0, direction).is_ok());
+        assert!(room.make_move(1, direction).is_ok());
+        assert!(room.game(0) == room.game(1));
+
+        let blocked = Direction::ALL.into_iter().find(|&direction| room.game(0).afterstate(direction).is_none());
+        if let Some(blocked) = blocked {
+            assert_eq!(room.make_move(0, blocked).err(), Some(RaceError::InvalidMove(blocked)));
+        }
+
+        assert_eq!(room.standings().iter().map(|standing| standing.moves).collect::<Vec<_>>(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_first_to_goal_wins() {
+        let mut room = Room::new(3, 2);
+        room.join("alice").unwrap();
+        room.join("bob").unwrap();
+        room.seats[1].game = Game::from_notation("1024,1024,0,0/0,0,0,0/0,0,0,0/0,0,0,0").unwrap();
+
+        assert!(room.make_move(1, Direction::Left).is_ok());
+        assert!(room.finished());
+        assert_eq!(room.winner(), Some(1));
+        assert_eq!(room.standings()[0].player, 1);
+        assert_eq!(room.make_move(0, first_move(room.game(0))).err(), Some(RaceError::Finished));
+    }
+
+    #[test]
+    fn test_best_score_wins_once_everyone_is_out() {
+        let mut room = Room::new(5, 3);
+        room.join("alice").unwrap();
+        room.leave(0);
+        room.join("bob").unwrap();
+        room.join("carol").unwrap();
+
+        // Players who left keep their place but can no longer move.
+        assert!(!room.finished());
+        assert_eq!(room.make_move(0, Direction::Up).err(), Some(RaceError::Out(0)));
+
+        room.seats[1].game = Game::from_notation("2,4,2,4/4,2,4,2/2,4,2,4/4,2,4,2 score=60").unwrap();
+        room.leave(2);
+        assert_eq!(room.winner(), Some(1));
+        assert!(room.standings().iter().all(|standing| standing.out));
+
+        let message = ServerMessage::Finished { winner: room.winner(), standings: room.standings() };
+        assert_eq!(ServerMessage::from_json(&message.to_json()).unwrap(), message);
+        assert_eq!(ClientMessage::from_json(r#"{"Move":{"direction":"Left"}}"#).unwrap(), ClientMessage::Move { direction: Direction::Left });
+    }
+}
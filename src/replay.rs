@@ -0,0 +1,451 @@
+//! Compact recordings of seeded games that can be played back and verified.
+//!
+//! A replay stores the seed and the list of moves, which is all that is needed to re-simulate the
+//! game. It also stores the tile spawned after every move along with the final board and score, so
+//! that playing it back on a different engine version pinpoints the first move at which the two
+//! disagree rather than just reporting a different outcome.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::notation::Position;
+use crate::{Direction, ExponentGrid, Game, GameConfig, InputResult, BOARD_DIMENSION};
+
+pub use crate::spawn::Spawn;
+
+/// Version of this crate, recorded in every replay.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(into = "EncodedReplay", try_from = "EncodedReplay")]
+pub struct Replay {
+    pub engine_version: String,
+    pub config: GameConfig,
+    pub seed: u64,
+    /// Position the game was started from, if it did not start from a new board.
+    pub start: Option<Position>,
+    /// Stored as one letter per move, see `Direction::letter()`.
+    pub moves: Vec<Direction>,
+    /// Stored as two characters per move: the slot of the spawned tile in row-major order as a hex
+    /// digit, followed by the `MergeRule::rank()` of its value under the replay's merge rule.
+    pub spawns: Vec<Spawn>,
+    /// Milliseconds after the first move at which each move was made. Empty for games recorded
+    /// without timing.
+    pub times: Vec<u32>,
+    pub final_score: u32,
+    pub final_board: ExponentGrid,
+}
+
+/// The stored form of a `Replay`. Spawned values can only be encoded and decoded under the merge
+/// rule of the replay's config, so the spawns are converted together with the rest of the replay.
+#[derive(Serialize, Deserialize)]
+struct EncodedReplay {
+    engine_version: String,
+    config: GameConfig,
+    seed: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start: Option<Position>,
+    #[serde(with = "move_list")]
+    moves: Vec<Direction>,
+    spawns: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    times: Vec<u32>,
+    final_score: u32,
+    final_board: ExponentGrid,
+}
+
+impl From<Replay> for EncodedReplay {
+    fn from(replay: Replay) -> EncodedReplay {
+        EncodedReplay {
+            spawns: spawn_list::encode(&replay.spawns, &replay.config),
+            engine_version: replay.engine_version,
+            config: replay.config,
+            seed: replay.seed,
+            start: replay.start,
+            moves: replay.moves,
+            times: replay.times,
+            final_score: replay.final_score,
+            final_board: replay.final_board,
+        }
+    }
+}
+
+impl TryFrom<EncodedReplay> for Replay {
+    type Error = String;
+
+    fn try_from(encoded: EncodedReplay) -> Result<Replay, String> {
+        Ok(Replay {
+            spawns: spawn_list::decode(&encoded.spawns, &encoded.config)?,
+            engine_version: encoded.engine_version,
+            config: encoded.config,
+            seed: encoded.seed,
+            start: encoded.start,
+            moves: encoded.moves,
+            times: encoded.times,
+            final_score: encoded.final_score,
+            final_board: encoded.final_board,
+        })
+    }
+}
+
+/// Where a replay stopped matching its re-simulation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Divergence {
+    /// The replay was recorded with rules this engine does not play by.
+    Config(GameConfig),
+    /// The move at `index` did not move any tiles.
+    InvalidMove { index: usize, direction: Direction },
+    /// The move at `index` spawned a different tile than the one recorded.
+    Spawn { index: usize, recorded: Spawn, actual: Spawn },
+    /// The replay has a different number of moves and spawns.
+    Truncated { moves: usize, spawns: usize },
+    /// Every move matched, but the final position did not.
+    FinalPosition { recorded_score: u32, actual_score: u32 },
+}
+
+impl Divergence {
+    /// Index of the first move that diverged, if the divergence can be attributed to one.
+    pub fn move_index(&self) -> Option<usize> {
+        match self {
+            Divergence::InvalidMove { index, .. } | Divergence::Spawn { index, .. } => Some(*index),
+            Divergence::Truncated { moves, spawns } => Some(*moves.min(spawns)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Divergence::Config(config) => write!(f, "unsupported game configuration {:?}", config),
+            Divergence::InvalidMove { index, direction } => {
+                write!(f, "move {} ({:?}) did not move any tiles", index + 1, direction)
+            },
+            Divergence::Spawn { index, recorded, actual } => {
+                write!(f, "move {} spawned a {} at ({}, {}) instead of a {} at ({}, {})",
+                       index + 1,
+                       actual.value, actual.row, actual.col,
+                       recorded.value, recorded.row, recorded.col)
+            },
+            Divergence::Truncated { moves, spawns } => {
+                write!(f, "replay has {} moves but {} spawns", moves, spawns)
+            },
+            Divergence::FinalPosition { recorded_score, actual_score } => {
+                write!(f, "final position differs (score {} instead of {})", actual_score, recorded_score)
+            },
+        }
+    }
+}
+
+impl Replay {
+    /// Plays `moves` on a new game seeded with `seed` and records the result. Moves that do not move
+    /// any tiles are left out, so raw player input can be passed in directly.
+    pub fn record(seed: u64, moves: &[Direction]) -> Replay {
+        Replay::record_from(None, seed, &GameConfig::default(), moves)
+    }
+
+    /// Same as `record()`, for a game started from `start` rather than from a new board, or played
+    /// under the rules of `config`, see `initial_game()`.
+    pub fn record_from(start: Option<Position>, seed: u64, config: &GameConfig, moves: &[Direction]) -> Replay {
+        let mut game = initial_game(start.as_ref(), seed, config);
+        let mut recorded_moves = Vec::with_capacity(moves.len());
+        let mut spawns = Vec::with_capacity(moves.len());
+
+        for &direction in moves {
+            if let Some(spawn) = make_move(&mut game, direction) {
+                recorded_moves.push(direction);
+                spawns.push(spawn);
+            }
+        }
+
+        Replay {
+            engine_version: ENGINE_VERSION.to_string(),
+            config: game.config(),
+            seed,
+            start,
+            moves: recorded_moves,
+            spawns,
+            times: Vec::new(),
+            final_score: game.score,
+            final_board: game.exponents(),
+        }
+    }
+
+    /// Adds the time at which each recorded move was made, in milliseconds after the first move.
+    /// Timings that do not match the recorded moves one to one are left out.
+    pub fn with_times(mut self, times: Vec<u32>) -> Replay {
+        if times.len() == self.moves.len() {
+            self.times = times;
+        }
+
+        self
+    }
+
+    /// Re-simulates the whole replay and returns the final game, or the first point at which the
+    /// re-simulation differs from the recording.
+    pub fn play_back(&self) -> Result<Game, Divergence> {
+        let game = self.play_back_to(self.moves.len())?;
+
+        if self.moves.len() != self.spawns.len() {
+            return Err(Divergence::Truncated { moves: self.moves.len(), spawns: self.spawns.len() })
+        }
+
+        if game.score != self.final_score || game.exponents() != self.final_board {
+            return Err(Divergence::FinalPosition { recorded_score: self.final_score, actual_score: game.score })
+        }
+
+        Ok(game)
+    }
+
+    /// Re-simulates the first `moves` moves of the replay, which is how viewers seek to a given
+    /// point in the game.
+    pub fn play_back_to(&self, moves: usize) -> Result<Game, Divergence> {
+        let mut game = initial_game(self.start.as_ref(), self.seed, &self.config);
+
+        if self.config != game.config() {
+            return Err(Divergence::Config(self.config.clone()))
+        }
+
+        for (index, &direction) in self.moves.iter().enumerate().take(moves) {
+            let Some(actual) = make_move(&mut game, direction) else {
+                return Err(Divergence::InvalidMove { index, direction })
+            };
+
+            match self.spawns.get(index) {
+                Some(&recorded) if recorded != actual => return Err(Divergence::Spawn { index, recorded, actual }),
+                Some(_) => (),
+                None => return Err(Divergence::Truncated { moves: self.moves.len(), spawns: self.spawns.len() }),
+            }
+        }
+
+        Ok(game)
+    }
+
+    /// Checks that the replay re-simulates to exactly the recorded game.
+    pub fn verify(&self) -> Result<(), Divergence> {
+        self.play_back().map(|_| ())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Replays always serialize.")
+    }
+
+    pub fn from_json(json: &str) -> Result<Replay, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Sets up the game a replay starts with: a new board, or `start`, with its spawns drawn from `seed`
+/// at the difficulty of `config` and played with its topology, edges and merge rule. Positions only
+/// hold doubling tiles, so games started from one always use that merge rule, and replays that say
+/// otherwise fail to play back with `Divergence::Config`.
+pub fn initial_game(start: Option<&Position>, seed: u64, config: &GameConfig) -> Game {
+    let spawner = config.difficulty.spawner(seed);
+    let mut game = match start {
+        Some(position) => position.to_game_with(spawner),
+        None => Game::with_merge_rule(spawner, config.merge_rule),
+    };
+
+    game.set_topology(config.topology);
+    game.set_edges(config.edges);
+    game
+}
+
+// Helper functions
+
+/// Makes a move and returns the tile it spawned, or `None` if nothing moved.
+fn make_move(game: &mut Game, direction: Direction) -> Option<Spawn> {
+    match game.make_move(direction) {
+        InputResult::Ok(new_id, tiles, _) => {
+            let new_id = new_id.expect("Replayed games always spawn a tile after a move.");
+            let tile = tiles.iter().find(|tile| tile.id == new_id).expect("Failed to find new Tile.");
+            Some(Spawn { row: tile.row, col: tile.col, value: tile.value })
+        },
+        InputResult::Err(_) => None,
+    }
+}
+
+mod move_list {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    use crate::Direction;
+
+    pub fn serialize<S: Serializer>(moves: &[Direction], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&moves.iter().map(|direction| direction.letter()).collect::<String>())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Direction>, D::Error> {
+        String::deserialize(deserializer)?.chars()
+            .map(|letter| Direction::from_letter(letter).ok_or_else(|| de::Error::custom(format!("invalid move {:?}", letter))))
+            .collect()
+    }
+}
+
+mod spawn_list {
+    use super::{Spawn, BOARD_DIMENSION};
+    use crate::GameConfig;
+
+    pub fn encode(spawns: &[Spawn], config: &GameConfig) -> String {
+        spawns.iter()
+            .map(|spawn| format!("{:x}{:x}", spawn.row * BOARD_DIMENSION + spawn.col, config.merge_rule.rank(spawn.value)))
+            .collect()
+    }
+
+    /// Reads back the spawns of `encoded`, which can only have taken the spawn values of `config`.
+    pub fn decode(encoded: &str, config: &GameConfig) -> Result<Vec<Spawn>, String> {
+        let digits: Vec<u32> = encoded.chars()
+            .map(|c| c.to_digit(16).ok_or_else(|| format!("invalid spawn {:?}", c)))
+            .collect::<Result<_, _>>()?;
+
+        if !digits.len().is_multiple_of(2) {
+            return Err(String::from("spawn list has an odd number of digits"))
+        }
+
+        digits.chunks(2)
+            .map(|spawn| {
+                let value = config.spawn_values.iter()
+                    .copied()
+                    .find(|&value| config.merge_rule.rank(value) == spawn[1])
+                    .ok_or_else(|| format!("no spawn value has rank {} under the {} merge rule", spawn[1], config.merge_rule.name()))?;
+
+                Ok(Spawn { row: spawn[0] as usize / BOARD_DIMENSION, col: spawn[0] as usize % BOARD_DIMENSION, value })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn::Difficulty;
+    use crate::{Edges, MergeRule, Topology};
+    use crate::strategy::{CornerStrategy, Strategy};
+
+    /// Moves of a complete game played by the corner strategy.
+    fn corner_game(seed: u64) -> Vec<Direction> {
+        let mut strategy = CornerStrategy::new();
+        let mut game = Game::with_seed(seed);
+        let mut moves = Vec::new();
+
+        while let Some(direction) = strategy.choose(&game) {
+            game.make_move(direction);
+            moves.push(direction);
+        }
+
+        moves
+    }
+
+    #[test]
+    fn test_record_and_play_back() {
+        let moves = corner_game(3);
+        let replay = Replay::record(3, &moves);
+
+        assert_eq!(replay.moves, moves);
+        assert_eq!(replay.engine_version, ENGINE_VERSION);
+
+        let game = replay.play_back().unwrap();
+        assert!(game.game_over());
+        assert_eq!(game.score, replay.final_score);
+
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        assert_eq!(loaded, replay);
+        assert_eq!(loaded.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_moves_are_not_recorded() {
+        // Sliding the same way repeatedly soon stops moving anything, and those moves must be left
+        // out of the recording.
+        let replay = Replay::record(9, &[Direction::Up, Direction::Up, Direction::Up, Direction::Left, Direction::Left]);
+
+        assert!(replay.moves.len() < 5);
+        assert_eq!(replay.moves.len(), replay.spawns.len());
+        assert_eq!(replay.verify(), Ok(()));
+    }
+
+    #[test]
+    /// Tampering with a replay should be reported at the exact move that no longer matches.
+    fn test_first_divergent_move() {
+        let replay = Replay::record(5, &corner_game(5));
+
+        let mut wrong_spawn = replay.clone();
+        let recorded = wrong_spawn.spawns[10];
+        wrong_spawn.spawns[10].value = if recorded.value == 2 { 4 } else { 2 };
+        assert_eq!(wrong_spawn.verify().unwrap_err().move_index(), Some(10));
+
+        let mut wrong_seed = replay.clone();
+        wrong_seed.seed += 1;
+        let divergence = wrong_seed.verify().unwrap_err();
+        assert!(matches!(divergence, Divergence::Spawn { index: 0, .. } | Divergence::InvalidMove { index: 0, .. }));
+
+        let mut wrong_score = replay;
+        wrong_score.final_score += 4;
+        assert!(matches!(wrong_score.verify(), Err(Divergence::FinalPosition { .. })));
+    }
+
+    #[test]
+    fn test_replay_from_position() {
+        let start: Position = "2,2,4,8/0,0,0,0/0,0,0,0/0,0,0,0 score=12 next=spawn".parse().unwrap();
+        let replay = Replay::record_from(Some(start.clone()), 4, &GameConfig::default(), &[Direction::Left, Direction::Down, Direction::Right]);
+
+        assert!(!replay.moves.is_empty());
+        assert_eq!(replay.play_back_to(0).unwrap().score, 12);
+
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        assert_eq!(loaded.start, Some(start));
+        assert_eq!(loaded.verify(), Ok(()));
+
+        let mut without_start = loaded;
+        without_start.start = None;
+        assert!(without_start.verify().is_err());
+    }
+
+    #[test]
+    fn test_replay_records_difficulty() {
+        let moves = corner_game(6);
+        let replay = Replay::record_from(None, 6, &GameConfig { difficulty: Difficulty::Expert, ..GameConfig::default() }, &moves);
+
+        assert_eq!(replay.config.difficulty, Difficulty::Expert);
+        assert!(replay.to_json().contains("\"difficulty\":\"expert\""));
+        assert_eq!(Replay::from_json(&replay.to_json()).unwrap().verify(), Ok(()));
+
+        // Replays recorded before difficulties existed are normal games.
+        let normal = Replay::record(6, &moves);
+        let old_json = normal.to_json().replace(",\"difficulty\":\"normal\"", "");
+        assert_eq!(Replay::from_json(&old_json).unwrap(), normal);
+
+        let mut wrong_difficulty = replay;
+        wrong_difficulty.config.difficulty = Difficulty::Normal;
+        assert!(wrong_difficulty.verify().is_err());
+    }
+
+    #[test]
+    fn test_replay_records_variant_rules() {
+        let config = GameConfig { topology: Topology::Hex, edges: Edges::Wrapped, merge_rule: MergeRule::Fibonacci, ..GameConfig::default() };
+        let moves: Vec<Direction> = Topology::Hex.directions().iter().copied().cycle().take(60).collect();
+        let replay = Replay::record_from(None, 8, &config, &moves);
+
+        assert_eq!((replay.config.topology, replay.config.edges, replay.config.merge_rule), (Topology::Hex, Edges::Wrapped, MergeRule::Fibonacci));
+        assert!(!replay.moves.is_empty());
+        assert_eq!(Replay::from_json(&replay.to_json()).unwrap().verify(), Ok(()));
+
+        // Played back under the classic rules, the same moves and spawns no longer match.
+        let mut square = replay;
+        square.config.topology = Topology::Square;
+        assert!(square.verify().is_err());
+    }
+
+    #[test]
+    fn test_tripling_spawns_round_trip() {
+        let config = GameConfig { merge_rule: MergeRule::Tripling, ..GameConfig::default() };
+        let moves: Vec<Direction> = Direction::ALL.iter().copied().cycle().take(40).collect();
+        let replay = Replay::record_from(None, 2, &config, &moves);
+
+        assert!(replay.spawns.iter().any(|spawn| spawn.value == 3));
+        assert!(replay.spawns.iter().all(|spawn| spawn.value == 3 || spawn.value == 9));
+
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        assert_eq!(loaded, replay);
+        assert_eq!(loaded.verify(), Ok(()));
+    }
+}
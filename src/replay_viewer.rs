@@ -0,0 +1,323 @@
+use gloo::storage::{LocalStorage, Storage};
+use gloo::timers::callback::Interval;
+use rust_2048::replay::Replay;
+use rust_2048::{Game, InputResult};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::*;
+
+// Time spent on each move while playing at normal speed, in milliseconds. Leaves a short pause after
+// the slide and expand animations.
+const REPLAY_STEP_DURATION: u64 = 400;
+
+const SPEEDS: [f64; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
+
+const STATUS_SELECTOR: &str = ".replay-status";
+
+pub enum ViewerCommand {
+    Load(Replay),
+    StepForward,
+    StepBack,
+    Seek(usize),
+    TogglePlay,
+    SetSpeed(f64),
+    /// Sent by the playback timer.
+    Tick,
+    /// Sent when the viewer is unmounted.
+    Close,
+}
+
+/// The replay being watched and how far into it the board is.
+struct Viewer {
+    replay: Option<Replay>,
+    game: Game,
+    position: usize,
+    speed: f64,
+    // Playback stops when the timer is dropped.
+    timer: Option<Interval>,
+}
+
+impl Viewer {
+    fn new() -> Self {
+        Viewer {
+            replay: None,
+            game: Game::with_seed(0),
+            position: 0,
+            speed: 1.0,
+            timer: None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.replay.as_ref().map_or(0, |replay| replay.moves.len())
+    }
+
+    /// Loads `replay` if it re-simulates to exactly the recorded game.
+    fn load(&mut self, replay: Replay) {
+        self.pause();
+
+        if let Err(divergence) = replay.verify() {
+            set_status(STATUS_SELECTOR, &format!("This replay does not match this version of the game: {}.", divergence));
+            return
+        }
+
+        set_status(STATUS_SELECTOR, &format!("Final score {} after {} moves.", replay.final_score, replay.moves.len()));
+        set_replay_text(&replay.to_json());
+
+        self.replay = Some(replay);
+        self.seek(0);
+    }
+
+    /// Animates the next move. Returns `false` if the end of the replay has been reached.
+    async fn step_forward(&mut self, animationend_rx: &mut counted_channel::CountedReceiver) -> bool {
+        let Some(&direction) = self.replay.as_ref().and_then(|replay| replay.moves.get(self.position)) else {
+            return false
+        };
+
+        if let InputResult::Ok(new_tile_id, tiles, _) = self.game.make_move(direction) {
+            animate_move(&board_container(), &tiles, new_tile_id, animationend_rx).await;
+        }
+
+        update_score(self.game.score);
+        self.position += 1;
+
+        true
+    }
+
+    /// Jumps to the board after `position` moves without animating the moves in between.
+    fn seek(&mut self, position: usize) {
+        let Some(replay) = &self.replay else {
+            return
+        };
+
+        let position = position.min(replay.moves.len());
+        self.game = replay.play_back_to(position).expect("Loaded replays have been verified.");
+        self.position = position;
+
+        render_board(&board_container(), &self.game);
+        update_score(self.game.score);
+    }
+
+    fn play(&mut self, command_tx: UnboundedSender<ViewerCommand>) {
+        if self.position >= self.len() {
+            self.seek(0);
+        }
+
+        let period = (REPLAY_STEP_DURATION as f64 / self.speed) as u32;
+
+        self.timer = Some(Interval::new(period, move || {
+            command_tx.send(ViewerCommand::Tick).unwrap();
+        }));
+    }
+
+    fn pause(&mut self) {
+        self.timer = None;
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+
+        *CURRENT_SLIDE_DURATION.lock().unwrap() = (DEFAULT_SLIDE_DURATION as f64 / speed) as u64;
+        *CURRENT_EXPAND_DURATION.lock().unwrap() = (DEFAULT_EXPAND_DURATION as f64 / speed) as u64;
+    }
+
+    /// Brings the move counter, scrubber and play button in line with the viewer's state.
+    fn update_controls(&self) {
+        let document = gloo::utils::document();
+
+        let move_counter = document.query_selector(".move-counter").unwrap().unwrap();
+        move_counter.set_inner_html(&format!("{} / {}", self.position, self.len()));
+
+        let scrubber = document.query_selector(".scrubber").unwrap().unwrap();
+        let scrubber = scrubber.dyn_ref::<HtmlInputElement>().unwrap();
+        scrubber.set_max(&self.len().to_string());
+        scrubber.set_value(&self.position.to_string());
+
+        let play_button = document.query_selector(".play").unwrap().unwrap();
+        play_button.set_inner_html(if self.timer.is_some() { "Pause" } else { "Play" });
+    }
+}
+
+async fn process_viewer_commands(mut command_rx: UnboundedReceiver<ViewerCommand>, command_tx: UnboundedSender<ViewerCommand>, mut animationend_rx: counted_channel::CountedReceiver) {
+    let mut viewer = Viewer::new();
+
+    while let Some(command) = command_rx.recv().await {
+        match command {
+            ViewerCommand::Load(replay) => viewer.load(replay),
+            ViewerCommand::StepForward => {
+                viewer.pause();
+                viewer.step_forward(&mut animationend_rx).await;
+            },
+            ViewerCommand::StepBack => {
+                viewer.pause();
+                viewer.seek(viewer.position.saturating_sub(1));
+            },
+            ViewerCommand::Seek(position) => {
+                viewer.pause();
+                viewer.seek(position);
+            },
+            ViewerCommand::TogglePlay => {
+                if viewer.timer.is_some() {
+                    viewer.pause();
+                } else {
+                    viewer.play(command_tx.clone());
+                }
+            },
+            ViewerCommand::SetSpeed(speed) => {
+                viewer.set_speed(speed);
+
+                if viewer.timer.is_some() {
+                    viewer.play(command_tx.clone());
+                }
+            },
+            // Ticks that were already queued when playback was paused are ignored.
+            ViewerCommand::Tick => {
+                if viewer.timer.is_some() && !viewer.step_forward(&mut animationend_rx).await {
+                    viewer.pause();
+                }
+            },
+            ViewerCommand::Close => {
+                viewer.pause();
+                viewer.set_speed(1.0);
+                break
+            },
+        }
+
+        viewer.update_controls();
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ReplayViewerProps {
+    pub close_callback: Callback<MouseEvent>,
+}
+
+/// Watches replays of the current session's game, of the last finished game saved in local storage,
+/// or of a pasted replay string.
+#[function_component(ReplayViewer)]
+pub fn replay_viewer(props: &ReplayViewerProps) -> Html {
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (animationend_tx, animationend_rx) = counted_channel::CountedChannel::new();
+
+    spawn_local(process_viewer_commands(command_rx, command_tx.clone(), animationend_rx));
+
+    {
+        let command_tx = command_tx.clone();
+
+        use_effect(move || {
+            // Start with the game that was just being played, falling back to the last saved one.
+            if let Some(replay) = session_replay().or_else(|| LocalStorage::get(REPLAY_STORAGE_KEY).ok()) {
+                command_tx.send(ViewerCommand::Load(replay)).unwrap();
+            }
+
+            let remove_listeners = add_game_listeners(None, animationend_tx);
+
+            move || {
+                remove_listeners();
+                command_tx.send(ViewerCommand::Close).unwrap();
+            }
+        });
+    }
+
+    let send = |command: fn() -> ViewerCommand| {
+        let command_tx = command_tx.clone();
+        Callback::from(move |_: MouseEvent| command_tx.send(command()).unwrap())
+    };
+
+    let step_back_callback = send(|| ViewerCommand::StepBack);
+    let play_callback = send(|| ViewerCommand::TogglePlay);
+    let step_forward_callback = send(|| ViewerCommand::StepForward);
+
+    let seek_callback = {
+        let command_tx = command_tx.clone();
+
+        Callback::from(move |event: InputEvent| {
+            let scrubber = event.target_dyn_into::<HtmlInputElement>().unwrap();
+            command_tx.send(ViewerCommand::Seek(scrubber.value().parse().unwrap_or(0))).unwrap();
+        })
+    };
+
+    let speed_callback = {
+        let command_tx = command_tx.clone();
+
+        Callback::from(move |event: Event| {
+            let select = event.target_dyn_into::<HtmlSelectElement>().unwrap();
+            command_tx.send(ViewerCommand::SetSpeed(select.value().parse().unwrap_or(1.0))).unwrap();
+        })
+    };
+
+    let session_callback = {
+        let command_tx = command_tx.clone();
+
+        Callback::from(move |_| match session_replay() {
+            Some(replay) => command_tx.send(ViewerCommand::Load(replay)).unwrap(),
+            None => set_status(STATUS_SELECTOR, "No moves have been made in this session yet."),
+        })
+    };
+
+    let saved_callback = {
+        let command_tx = command_tx.clone();
+
+        Callback::from(move |_| match LocalStorage::get(REPLAY_STORAGE_KEY) {
+            Ok(replay) => command_tx.send(ViewerCommand::Load(replay)).unwrap(),
+            Err(_) => set_status(STATUS_SELECTOR, "No saved replay was found."),
+        })
+    };
+
+    let pasted_callback = Callback::from(move |_| {
+        let document = gloo::utils::document();
+        let replay_text = document.query_selector(".replay-text").unwrap().unwrap();
+        let replay_text = replay_text.dyn_ref::<HtmlTextAreaElement>().unwrap();
+
+        match Replay::from_json(replay_text.value().trim()) {
+            Ok(replay) => command_tx.send(ViewerCommand::Load(replay)).unwrap(),
+            Err(error) => set_status(STATUS_SELECTOR, &format!("This is not a valid replay: {}.", error)),
+        }
+    });
+
+    let style_args = button_style_args();
+    let status_style = format!("--status_text: {}", COLORS.text_light);
+
+    html! {
+        <div class="content replay-viewer">
+            <div class="metadata-container">
+                <Score score={0}/>
+                <div class="metadata move-counter" style={style_args.clone()}>{ "0 / 0" }</div>
+                <NewGameButton onclick={props.close_callback.clone()} button_text={"Back to Game"} disabled={false}/>
+            </div>
+            <div class="board-container">
+                <GameBoard/>
+            </div>
+            <div class="replay-controls">
+                <button class="metadata" onclick={step_back_callback} style={style_args.clone()}>{ "<" }</button>
+                <button class="metadata play" onclick={play_callback} style={style_args.clone()}>{ "Play" }</button>
+                <button class="metadata" onclick={step_forward_callback} style={style_args.clone()}>{ ">" }</button>
+                <input type="range" class="scrubber" min="0" max="0" value="0" oninput={seek_callback}/>
+                <select class="metadata speed" onchange={speed_callback} style={style_args.clone()}>
+                    { for SPEEDS.iter().map(|&speed| html! {
+                        <option value={speed.to_string()} selected={speed == 1.0}>{ format!("{}x", speed) }</option>
+                    })}
+                </select>
+            </div>
+            <div class="replay-source">
+                <button class="metadata" onclick={session_callback} style={style_args.clone()}>{ "This Game" }</button>
+                <button class="metadata" onclick={saved_callback} style={style_args.clone()}>{ "Last Saved" }</button>
+                <button class="metadata" onclick={pasted_callback} style={style_args}>{ "Load Pasted" }</button>
+            </div>
+            <textarea class="replay-text" placeholder="Paste a replay here"/>
+            <div class="replay-status" style={status_style}/>
+        </div>
+    }
+}
+
+// Helper functions
+
+fn set_replay_text(text: &str) {
+    let document = gloo::utils::document();
+    let replay_text = document.query_selector(".replay-text").unwrap().unwrap();
+    replay_text.dyn_ref::<HtmlTextAreaElement>().unwrap().set_value(text);
+}
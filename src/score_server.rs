@@ -0,0 +1,413 @@
+//! High scores that are only accepted along with a replay proving them.
+//!
+//! A submission claims a score and a max tile and carries the replay of the game. It is accepted
+//! only if the replay re-simulates to exactly the recorded game and that game has the claimed
+//! score and max tile. Only games played under the classic rules are ranked, at any difficulty.
+//! Accepted scores are kept per mode (the difficulty) and board size in a
+//! directory: `scores.json` lists every accepted score and `replays/ID.json` holds each replay.
+//!
+//! Requests are answered with JSON:
+//!
+//! - `POST /scores` with a `Submission` adds a score and answers with its `ScoreEntry`.
+//! - `GET /leaderboards/MODE/SIZE?limit=N` lists the best scores for a mode (e.g. `normal`) and
+//!   board size, best first.
+//! - `GET /replays/ID` fetches the replay of a score.
+//!
+//! The rules and the request handling are always built; the HTTP server itself needs the `server`
+//! feature.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::replay::{Divergence, Replay};
+use crate::spawn::Difficulty;
+use crate::GameConfig;
+
+/// Longest player name accepted, in characters.
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// Number of scores listed when a leaderboard request does not ask for a number.
+pub const DEFAULT_LEADERBOARD_LIMIT: usize = 10;
+
+/// Most scores a leaderboard request can ask for.
+pub const MAX_LEADERBOARD_LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Submission {
+    pub name: String,
+    pub score: u32,
+    pub max_tile: u32,
+    pub replay: Replay,
+}
+
+/// An accepted score, without its replay.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ScoreEntry {
+    pub id: u64,
+    pub name: String,
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: usize,
+    pub mode: Difficulty,
+    pub board_size: usize,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubmissionError {
+    /// The name is empty or longer than `MAX_NAME_LENGTH`.
+    Name,
+    /// The game was started from a custom position, which could have been set up with any score.
+    CustomStart,
+    /// The game was played on another topology, with wrapped edges or with another merge rule, so
+    /// its score cannot be compared with classic ones.
+    Variant,
+    /// The replay does not re-simulate to the recorded game.
+    Replay(Divergence),
+    /// The replayed game ended with a different score than the one claimed.
+    Score { claimed: u32, actual: u32 },
+    /// The replayed game ended with a different max tile than the one claimed.
+    MaxTile { claimed: u32, actual: u32 },
+}
+
+impl fmt::Display for SubmissionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmissionError::Name => write!(f, "names must have between 1 and {} characters", MAX_NAME_LENGTH),
+            SubmissionError::CustomStart => write!(f, "games started from a custom position are not ranked"),
+            SubmissionError::Variant => write!(f, "games played under variant rules are not ranked"),
+            SubmissionError::Replay(divergence) => write!(f, "the replay does not check out: {}", divergence),
+            SubmissionError::Score { claimed, actual } => write!(f, "claimed a score of {} but the replay scores {}", claimed, actual),
+            SubmissionError::MaxTile { claimed, actual } => write!(f, "claimed a {} tile but the replay reaches {}", claimed, actual),
+        }
+    }
+}
+
+impl std::error::Error for SubmissionError {}
+
+/// A submission whose replay has been checked. Only `Submission::verify()` makes these, so only
+/// proven scores can reach a `ScoreStore`.
+pub struct VerifiedSubmission(Submission);
+
+impl Submission {
+    /// Re-simulates the replay and checks it against the claimed score and max tile.
+    pub fn verify(self) -> Result<VerifiedSubmission, SubmissionError> {
+        let name_length = self.name.trim().chars().count();
+
+        if name_length == 0 || name_length > MAX_NAME_LENGTH {
+            return Err(SubmissionError::Name)
+        }
+
+        if self.replay.start.is_some() {
+            return Err(SubmissionError::CustomStart)
+        }
+
+        if self.replay.config != (GameConfig { difficulty: self.replay.config.difficulty, ..GameConfig::default() }) {
+            return Err(SubmissionError::Variant)
+        }
+
+        let game = self.replay.play_back().map_err(SubmissionError::Replay)?;
+
+        if game.score != self.score {
+            return Err(SubmissionError::Score { claimed: self.score, actual: game.score })
+        }
+
+        if game.max_tile() != self.max_tile {
+            return Err(SubmissionError::MaxTile { claimed: self.max_tile, actual: game.max_tile() })
+        }
+
+        Ok(VerifiedSubmission(self))
+    }
+}
+
+/// Accepted scores, kept in a directory.
+pub struct ScoreStore {
+    directory: PathBuf,
+    entries: Vec<ScoreEntry>,
+}
+
+impl ScoreStore {
+    /// Opens the store in `directory`, creating it if needed.
+    pub fn open(directory: impl AsRef<Path>) -> io::Result<ScoreStore> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(directory.join("replays"))?;
+
+        let entries = match fs::read_to_string(directory.join("scores.json")) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|error| io::Error::new(ErrorKind::InvalidData, error))?,
+            Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+
+        Ok(ScoreStore { directory, entries })
+    }
+
+    /// Saves a score and its replay, and returns the score's entry.
+    pub fn insert(&mut self, submission: VerifiedSubmission) -> io::Result<ScoreEntry> {
+        let VerifiedSubmission(submission) = submission;
+
+        let entry = ScoreEntry {
+            id: self.entries.iter().map(|entry| entry.id + 1).max().unwrap_or(1),
+            name: submission.name.trim().to_string(),
+            score: submission.score,
+            max_tile: submission.max_tile,
+            moves: submission.replay.moves.len(),
+            mode: submission.replay.config.difficulty,
+            board_size: submission.replay.config.board_size,
+            seed: submission.replay.seed,
+        };
+
+        // The replay is written first, so that every listed score has one.
+        fs::write(self.replay_path(entry.id), submission.replay.to_json())?;
+        self.entries.push(entry.clone());
+
+        let scores = serde_json::to_string(&self.entries).expect("Scores always serialize.");
+        fs::write(self.directory.join("scores.json"), scores)?;
+
+        Ok(entry)
+    }
+
+    /// The best `limit` scores for a mode and board size, best first. Equal scores are listed in
+    /// the order they were submitted.
+    pub fn leaderboard(&self, mode: Difficulty, board_size: usize, limit: usize) -> Vec<ScoreEntry> {
+        let mut entries: Vec<ScoreEntry> = self.entries.iter()
+            .filter(|entry| entry.mode == mode && entry.board_size == board_size)
+            .cloned()
+            .collect();
+
+        entries.sort_by_key(|entry| (std::cmp::Reverse(entry.score), entry.id));
+        entries.truncate(limit);
+        entries
+    }
+
+    /// The replay of the score with the given id, or `None` if there is no such score.
+    pub fn replay(&self, id: u64) -> io::Result<Option<Replay>> {
+        match fs::read_to_string(self.replay_path(id)) {
+            Ok(contents) => Replay::from_json(&contents).map(Some).map_err(|error| io::Error::new(ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn replay_path(&self, id: u64) -> PathBuf {
+        self.directory.join("replays").join(format!("{}.json", id))
+    }
+}
+
+/// Status code and JSON body answering a request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn json(status: u16, value: &impl Serialize) -> Response {
+        Response { status, body: serde_json::to_string(value).expect("Responses always serialize.") }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+/// Answers a request for `url` (path and query) with the given method and body.
+pub fn respond(store: &mut ScoreStore, method: &str, url: &str, body: &str) -> Response {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+    match (method, segments.as_slice()) {
+        ("POST", ["scores"]) => {
+            let submission: Submission = match serde_json::from_str(body) {
+                Ok(submission) => submission,
+                Err(error) => return Response::error(400, &error.to_string()),
+            };
+
+            match submission.verify() {
+                Ok(verified) => match store.insert(verified) {
+                    Ok(entry) => Response::json(201, &entry),
+                    Err(error) => Response::error(500, &error.to_string()),
+                },
+                Err(error) => Response::error(422, &error.to_string()),
+            }
+        },
+        ("GET", ["leaderboards", mode, board_size]) => {
+            let Some(mode) = Difficulty::ALL.into_iter().find(|difficulty| difficulty.name().eq_ignore_ascii_case(mode)) else {
+                return Response::error(404, "unknown mode")
+            };
+
+            let Ok(board_size) = board_size.parse() else {
+                return Response::error(404, "unknown board size")
+            };
+
+            let limit = query.split('&')
+                .find_map(|parameter| parameter.strip_prefix("limit="))
+                .and_then(|limit| limit.parse().ok())
+                .unwrap_or(DEFAULT_LEADERBOARD_LIMIT)
+                .min(MAX_LEADERBOARD_LIMIT);
+
+            Response::json(200, &store.leaderboard(mode, board_size, limit))
+        },
+        ("GET", ["replays", id]) => {
+            let Ok(id) = id.parse() else {
+                return Response::error(404, "no such score")
+            };
+
+            match store.replay(id) {
+                Ok(Some(replay)) => Response { status: 200, body: replay.to_json() },
+                Ok(None) => Response::error(404, "no such score"),
+                Err(error) => Response::error(500, &error.to_string()),
+            }
+        },
+        (_, ["scores"] | ["leaderboards", _, _] | ["replays", _]) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+#[cfg(feature = "server")]
+pub use network::serve;
+
+#[cfg(feature = "server")]
+mod network {
+    use std::io::Read;
+
+    use super::{respond, Response, ScoreStore};
+
+    // Largest request body read, in bytes. Replays take about three bytes per move.
+    const MAX_BODY: u64 = 4 << 20;
+
+    /// Answers requests to `server` one at a time, until it is shut down.
+    pub fn serve(server: tiny_http::Server, mut store: ScoreStore) {
+        let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json").unwrap();
+
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+
+            let response = match request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+                Ok(_) => respond(&mut store, request.method().as_str(), request.url(), &body),
+                Err(_) => Response::error(400, "the body is not valid UTF-8"),
+            };
+
+            let http_response = tiny_http::Response::from_string(response.body)
+                .with_status_code(response.status)
+                .with_header(content_type.clone());
+
+            // A client that hung up does not need an answer.
+            let _ = request.respond(http_response);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, MergeRule};
+
+    fn submission(name: &str) -> Submission {
+        let replay = Replay::record(42, &[Direction::Left, Direction::Up, Direction::Right, Direction::Down, Direction::Left, Direction::Up]);
+        let game = replay.play_back().unwrap();
+
+        Submission { name: name.to_string(), score: game.score, max_tile: game.max_tile(), replay }
+    }
+
+    fn temporary_store(name: &str) -> (PathBuf, ScoreStore) {
+        let directory = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let store = ScoreStore::open(&directory).unwrap();
+
+        (directory, store)
+    }
+
+    #[test]
+    fn test_only_proven_scores_are_accepted() {
+        assert!(submission("alice").verify().is_ok());
+
+        let mut inflated = submission("alice");
+        inflated.score += 4;
+        assert!(matches!(inflated.verify(), Err(SubmissionError::Score { .. })));
+
+        let mut inflated = submission("alice");
+        inflated.max_tile = 2048;
+        assert!(matches!(inflated.verify(), Err(SubmissionError::MaxTile { .. })));
+
+        // Claiming a tampered replay's own score does not help either.
+        let mut tampered = submission("alice");
+        tampered.replay.final_score += 4;
+        tampered.score += 4;
+        assert!(matches!(tampered.verify(), Err(SubmissionError::Replay(_))));
+
+        assert!(matches!(submission(" ").verify(), Err(SubmissionError::Name)));
+    }
+
+    #[test]
+    /// Games played under variant rules score differently and are kept off the leaderboards.
+    fn test_variant_games_are_not_ranked() {
+        let config = GameConfig { merge_rule: MergeRule::Fibonacci, ..GameConfig::default() };
+        let replay = Replay::record_from(None, 42, &config, &[Direction::Left, Direction::Up, Direction::Right, Direction::Down]);
+        let game = replay.play_back().unwrap();
+        let variant = Submission { name: "carol".to_string(), score: game.score, max_tile: game.max_tile(), replay };
+
+        assert!(matches!(variant.clone().verify(), Err(SubmissionError::Variant)));
+
+        let (directory, mut store) = temporary_store("score_variants");
+        assert_eq!(respond(&mut store, "POST", "/scores", &serde_json::to_string(&variant).unwrap()).status, 422);
+        assert!(store.leaderboard(Difficulty::Normal, 4, 10).is_empty());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_store_keeps_scores_and_replays() {
+        let (directory, mut store) = temporary_store("score_store");
+
+        let mut better = submission("bob");
+        better.replay = Replay::record(7, &[Direction::Left, Direction::Right, Direction::Left, Direction::Right, Direction::Up, Direction::Down, Direction::Up]);
+        let game = better.replay.play_back().unwrap();
+        better.score = game.score;
+        better.max_tile = game.max_tile();
+
+        let first = store.insert(submission("alice").verify().unwrap()).unwrap();
+        let second = store.insert(better.clone().verify().unwrap()).unwrap();
+        assert_ne!(first.id, second.id);
+
+        // Scores and replays survive reopening the store.
+        let store = ScoreStore::open(&directory).unwrap();
+        let leaderboard = store.leaderboard(Difficulty::Normal, 4, 10);
+        assert_eq!(leaderboard.len(), 2);
+        assert!(leaderboard[0].score >= leaderboard[1].score);
+        assert_eq!(store.leaderboard(Difficulty::Normal, 4, 1).len(), 1);
+        assert!(store.leaderboard(Difficulty::Hard, 4, 10).is_empty());
+        assert_eq!(store.replay(second.id).unwrap(), Some(better.replay));
+        assert_eq!(store.replay(99).unwrap(), None);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_requests() {
+        let (directory, mut store) = temporary_store("score_requests");
+
+        let response = respond(&mut store, "POST", "/scores", &serde_json::to_string(&submission("alice")).unwrap());
+        assert_eq!(response.status, 201);
+        let entry: ScoreEntry = serde_json::from_str(&response.body).unwrap();
+
+        let mut inflated = submission("mallory");
+        inflated.score *= 2;
+        assert_eq!(respond(&mut store, "POST", "/scores", &serde_json::to_string(&inflated).unwrap()).status, 422);
+        assert_eq!(respond(&mut store, "POST", "/scores", "{").status, 400);
+
+        let response = respond(&mut store, "GET", "/leaderboards/normal/4?limit=5", "");
+        assert_eq!(response.status, 200);
+        assert_eq!(serde_json::from_str::<Vec<ScoreEntry>>(&response.body).unwrap(), vec![entry.clone()]);
+        assert_eq!(respond(&mut store, "GET", "/leaderboards/easy/4", "").status, 404);
+
+        let response = respond(&mut store, "GET", &format!("/replays/{}", entry.id), "");
+        assert_eq!(Replay::from_json(&response.body).unwrap(), submission("alice").replay);
+        assert_eq!(respond(&mut store, "GET", "/replays/99", "").status, 404);
+        assert_eq!(respond(&mut store, "DELETE", "/scores", "").status, 405);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,344 @@
+//! Providers that decide which tile spawns after every move.
+//!
+//! A game asks its provider for the two starting tiles and then for one tile after every move that
+//! moved something. Providers are chosen when the game is created with `Game::with_spawner()`, or
+//! swapped later with `Game::set_spawner()`.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::VecDeque;
+
+use crate::ai::{Evaluator, HeuristicWeights};
+use crate::rng::SeededRng;
+use crate::Game;
+
+/// A tile spawned on the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spawn {
+    pub row: usize,
+    pub col: usize,
+    pub value: u32,
+}
+
+pub trait SpawnProvider: DynSpawnProvider + Send + Sync {
+    /// Chooses the tile to spawn on `game`'s board, or `None` to spawn nothing. Spawns on an
+    /// occupied slot are skipped.
+    fn next_spawn(&mut self, game: &Game) -> Option<Spawn>;
+
+    /// Chooses the starting tiles of a new game on the empty board of `game`. Defaults to two
+    /// consecutive spawns.
+    fn opening(&mut self, game: &Game) -> Vec<Spawn> {
+        let mut board = game.clone();
+        let mut spawns = Vec::new();
+
+        for _ in 0..2 {
+            if let Some(spawn) = self.next_spawn(&board) {
+                board.place_tile(spawn.row, spawn.col, spawn.value);
+                spawns.push(spawn);
+            }
+        }
+
+        spawns
+    }
+
+    /// Difficulty recorded in the configuration of games using this provider.
+    fn difficulty(&self) -> Difficulty {
+        Difficulty::Normal
+    }
+}
+
+/// How often spawns are chosen to hurt the player rather than at random.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    #[default]
+    Normal,
+    Hard,
+    Expert,
+    Evil,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Normal, Difficulty::Hard, Difficulty::Expert, Difficulty::Evil];
+
+    /// Share of spawns placed by the adversary, from 0 for normal games to 1 for every spawn.
+    pub fn adversary_share(self) -> f64 {
+        match self {
+            Difficulty::Normal => 0.0,
+            Difficulty::Hard => 0.25,
+            Difficulty::Expert => 0.6,
+            Difficulty::Evil => 1.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+            Difficulty::Evil => "Evil",
+        }
+    }
+
+    /// Inverse of `name()`.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|difficulty| difficulty.name() == name)
+    }
+
+    /// Provider of seeded spawns at this difficulty. Normal games spawn exactly as
+    /// `Game::with_seed()` does.
+    pub fn spawner(self, seed: u64) -> Box<dyn SpawnProvider> {
+        match self {
+            Difficulty::Normal => Box::new(SeededSpawner::new(seed)),
+            difficulty => Box::new(AdversarialSpawner::new(seed, difficulty)),
+        }
+    }
+}
+
+/// Lets boxed providers be cloned and compared along with the game that owns them. Implemented for
+/// every provider that is `Clone` and `PartialEq`.
+pub trait DynSpawnProvider {
+    fn clone_box(&self) -> Box<dyn SpawnProvider>;
+    fn eq_dyn(&self, other: &dyn SpawnProvider) -> bool;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: SpawnProvider + Clone + PartialEq + 'static> DynSpawnProvider for T {
+    fn clone_box(&self) -> Box<dyn SpawnProvider> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn SpawnProvider) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The provider owned by a game.
+pub(crate) struct Spawner(pub(crate) Box<dyn SpawnProvider>);
+
+impl Clone for Spawner {
+    fn clone(&self) -> Self {
+        Spawner(self.0.clone_box())
+    }
+}
+
+impl PartialEq for Spawner {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_dyn(other.0.as_ref())
+    }
+}
+
+/// Spawns a 2 or a 4 in a random free slot, drawn from the thread-local generator. This is how
+/// normal games are played.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RandomSpawner;
+
+impl SpawnProvider for RandomSpawner {
+    fn next_spawn(&mut self, game: &Game) -> Option<Spawn> {
+        game.random_spawn(&mut rand::thread_rng())
+    }
+
+    fn opening(&mut self, game: &Game) -> Vec<Spawn> {
+        game.random_opening(&mut rand::thread_rng())
+    }
+}
+
+/// Same as `RandomSpawner`, except that every choice is drawn from a generator seeded with `seed`,
+/// so games with the same seed and moves are identical.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeededSpawner {
+    rng: SeededRng,
+}
+
+impl SeededSpawner {
+    pub fn new(seed: u64) -> Self {
+        SeededSpawner { rng: SeededRng::new(seed) }
+    }
+}
+
+impl SpawnProvider for SeededSpawner {
+    fn next_spawn(&mut self, game: &Game) -> Option<Spawn> {
+        game.random_spawn(&mut self.rng)
+    }
+
+    fn opening(&mut self, game: &Game) -> Vec<Spawn> {
+        game.random_opening(&mut self.rng)
+    }
+}
+
+/// Places a share of spawns, set by the difficulty, where they hurt the player most and draws the
+/// rest like `SeededSpawner`. An adversarial spawn is the tile and slot after which the player's
+/// best move leads to the lowest evaluation, or leaves no move at all.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdversarialSpawner {
+    difficulty: Difficulty,
+    rng: SeededRng,
+    evaluator: HeuristicWeights,
+}
+
+impl AdversarialSpawner {
+    pub fn new(seed: u64, difficulty: Difficulty) -> Self {
+        AdversarialSpawner {
+            difficulty,
+            rng: SeededRng::new(seed),
+            evaluator: HeuristicWeights::default(),
+        }
+    }
+
+    /// The spawn that minimizes the value of the player's best reply.
+    fn worst_spawn(&self, game: &Game) -> Option<Spawn> {
+        let mut worst: Option<(f64, Spawn)> = None;
+
+        for (row, col) in game.empty_slots() {
+            for value in game.config().spawn_values {
+                let mut board = game.clone();
+                board.place_tile(row, col, value);
+
+                let reply = board.topology().directions().iter()
+                    .filter_map(|&direction| board.afterstate(direction))
+                    .map(|(afterstate, reward)| reward as f64 + self.evaluator.evaluate(&afterstate))
+                    .fold(f64::NEG_INFINITY, f64::max);
+
+                if worst.is_none_or(|(worst_reply, _)| reply < worst_reply) {
+                    worst = Some((reply, Spawn { row, col, value }));
+                }
+            }
+        }
+
+        worst.map(|(_, spawn)| spawn)
+    }
+}
+
+impl SpawnProvider for AdversarialSpawner {
+    fn next_spawn(&mut self, game: &Game) -> Option<Spawn> {
+        if self.rng.gen::<f64>() < self.difficulty.adversary_share() {
+            self.worst_spawn(game)
+        } else {
+            game.random_spawn(&mut self.rng)
+        }
+    }
+
+    /// The starting tiles are always random, since there is nothing to play against yet.
+    fn opening(&mut self, game: &Game) -> Vec<Spawn> {
+        game.random_opening(&mut self.rng)
+    }
+
+    fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+}
+
+/// Spawns a fixed sequence of tiles, in order, including the starting tiles of a new game. Nothing
+/// spawns once the sequence runs out.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScriptedSpawner {
+    queue: VecDeque<Spawn>,
+}
+
+impl ScriptedSpawner {
+    pub fn new(spawns: impl IntoIterator<Item = Spawn>) -> Self {
+        ScriptedSpawner { queue: spawns.into_iter().collect() }
+    }
+
+    /// Adds a spawn to the end of the sequence.
+    pub fn push(&mut self, spawn: Spawn) {
+        self.queue.push_back(spawn);
+    }
+
+    /// Number of spawns left in the sequence.
+    pub fn remaining(&self) -> usize {
+        self.queue.len()
+    }
+}
+
+impl SpawnProvider for ScriptedSpawner {
+    fn next_spawn(&mut self, _game: &Game) -> Option<Spawn> {
+        self.queue.pop_front()
+    }
+}
+
+/// Never spawns anything, for positions that should only change by sliding.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NoSpawner;
+
+impl SpawnProvider for NoSpawner {
+    fn next_spawn(&mut self, _game: &Game) -> Option<Spawn> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, InputResult};
+
+    #[test]
+    fn test_scripted_spawns() {
+        let script = [
+            Spawn { row: 0, col: 0, value: 2 },
+            Spawn { row: 0, col: 1, value: 2 },
+            Spawn { row: 3, col: 3, value: 4 },
+        ];
+        let mut game = Game::with_spawner(Box::new(ScriptedSpawner::new(script)));
+
+        assert_eq!(game.exponents(), [[1, 1, 0, 0], [0; 4], [0; 4], [0; 4]]);
+
+        assert!(matches!(game.make_move(Direction::Left), InputResult::Ok(Some(_), _, _)));
+        assert_eq!(game.exponents(), [[2, 0, 0, 0], [0; 4], [0; 4], [0, 0, 0, 2]]);
+
+        // The script has run out, so the next move spawns nothing.
+        assert!(matches!(game.make_move(Direction::Right), InputResult::Ok(None, _, _)));
+        assert_eq!(game.exponents(), [[0, 0, 0, 2], [0; 4], [0; 4], [0, 0, 0, 2]]);
+    }
+
+    #[test]
+    fn test_spawners_compare_with_games() {
+        let mut game = Game::from_exponents(&[[1, 1, 0, 0], [0; 4], [0; 4], [0; 4]]);
+        game.set_spawner(Box::new(NoSpawner));
+
+        let copy = game.clone();
+        game.make_move(Direction::Left);
+        assert_eq!(game.get_tiles().len(), 1);
+
+        game.set_spawner(Box::new(SeededSpawner::new(1)));
+        assert!(game != copy);
+        assert!(Game::with_seed(3) == Game::with_seed(3));
+        assert!(Game::with_seed(3) != Game::with_spawner(Box::new(ScriptedSpawner::new(Game::with_seed(3).get_tiles().iter().map(|tile| Spawn { row: tile.row, col: tile.col, value: tile.value })))));
+    }
+
+    #[test]
+    /// The adversary should pick the spawn that leaves the player worst off, here the tile that
+    /// blocks every move: a 2 would merge with its neighbours, a 4 does not.
+    fn test_adversarial_spawns() {
+        let mut game = Game::from_notation("2,4,2,4/4,2,4,2/2,4,2,4/4,2,0,2").unwrap();
+        let evil = AdversarialSpawner::new(1, Difficulty::Evil);
+        assert_eq!(evil.worst_spawn(&game), Some(Spawn { row: 3, col: 2, value: 4 }));
+
+        game.set_spawner(Box::new(evil));
+        assert_eq!(game.config().difficulty, Difficulty::Evil);
+        game.spawn_tile();
+        assert!(game.game_over());
+
+        // Evil games end sooner than normal ones on average.
+        let moves = |difficulty: Difficulty, seed: u64| {
+            let mut game = Game::with_spawner(difficulty.spawner(seed));
+            let mut moves = 0;
+
+            while let Some(direction) = crate::ai::best_move(&game, &HeuristicWeights::default()) {
+                game.make_move(direction);
+                moves += 1;
+            }
+
+            moves
+        };
+
+        let normal: usize = (0..3).map(|seed| moves(Difficulty::Normal, seed)).sum();
+        let evil: usize = (0..3).map(|seed| moves(Difficulty::Evil, seed)).sum();
+        assert!(evil < normal, "{} evil moves, {} normal moves", evil, normal);
+    }
+}
@@ -0,0 +1,540 @@
+//! Players that pick moves for a `Game`, behind one common `Strategy` trait.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+
+use crate::ai::{self, Evaluator, HeuristicWeights};
+use crate::transposition::{self, TranspositionTable};
+use crate::{parallel, Direction, Game, InputResult};
+
+/// Value of a position in which no move is possible, used by the search-based strategies.
+const GAME_OVER_VALUE: f64 = -100_000.0;
+
+pub trait Strategy {
+    /// Short, human-readable name used in reports.
+    fn name(&self) -> String;
+
+    /// Picks the next move. Returns `None` if no move is possible.
+    fn choose(&mut self, game: &Game) -> Option<Direction>;
+}
+
+/// Names accepted by `by_name()`.
+pub const STRATEGY_NAMES: [&str; 6] = ["random", "greedy", "corner", "snake", "expectimax", "montecarlo"];
+
+/// Builds a built-in strategy with its default settings from one of `STRATEGY_NAMES`.
+pub fn by_name(name: &str) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomStrategy::new())),
+        "greedy" => Some(Box::new(GreedyStrategy)),
+        "corner" => Some(Box::new(CornerStrategy::new())),
+        "snake" => Some(Box::new(SnakeStrategy::new())),
+        "expectimax" => Some(Box::new(ExpectimaxStrategy::new(2))),
+        "montecarlo" => Some(Box::new(MonteCarloStrategy::new(50))),
+        _ => None,
+    }
+}
+
+/// Returns every direction in which a move is currently possible.
+pub fn valid_moves(game: &Game) -> Vec<Direction> {
+    game.topology().directions().iter().copied().filter(|&direction| game.afterstate(direction).is_some()).collect()
+}
+
+/// Picks uniformly at random among the valid moves.
+pub struct RandomStrategy {
+    rng: StdRng,
+}
+
+impl RandomStrategy {
+    pub fn new() -> Self {
+        RandomStrategy { rng: StdRng::from_entropy() }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        RandomStrategy { rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Default for RandomStrategy {
+    fn default() -> Self {
+        RandomStrategy::new()
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn name(&self) -> String {
+        String::from("random")
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        valid_moves(game).choose(&mut self.rng).copied()
+    }
+}
+
+/// Picks whichever move scores the most points right now. Ties go to the earliest direction in
+/// `Topology::directions()`.
+pub struct GreedyStrategy;
+
+impl Strategy for GreedyStrategy {
+    fn name(&self) -> String {
+        String::from("greedy")
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        let mut best: Option<(u32, Direction)> = None;
+
+        for &direction in game.topology().directions() {
+            if let Some((_, reward)) = game.afterstate(direction) {
+                if best.is_none_or(|(best_reward, _)| reward > best_reward) {
+                    best = Some((reward, direction));
+                }
+            }
+        }
+
+        best.map(|(_, direction)| direction)
+    }
+}
+
+/// Plays the first valid move from a fixed priority order, which keeps large tiles hugging one
+/// corner. The default order favours the bottom-left corner. Boards with more directions than the
+/// four in the order, such as hex boards, fall back to their other directions.
+pub struct CornerStrategy {
+    priority: [Direction; 4],
+}
+
+impl CornerStrategy {
+    pub fn new() -> Self {
+        CornerStrategy::with_priority([Direction::Down, Direction::Left, Direction::Right, Direction::Up])
+    }
+
+    pub fn with_priority(priority: [Direction; 4]) -> Self {
+        CornerStrategy { priority }
+    }
+}
+
+impl Default for CornerStrategy {
+    fn default() -> Self {
+        CornerStrategy::new()
+    }
+}
+
+impl Strategy for CornerStrategy {
+    fn name(&self) -> String {
+        String::from("corner")
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        self.priority.into_iter()
+            .find(|&direction| game.afterstate(direction).is_some())
+            .or_else(|| valid_moves(game).first().copied())
+    }
+}
+
+/// Looks one move ahead and keeps tiles ordered along a snake, using `HeuristicWeights::snake()`.
+pub struct SnakeStrategy {
+    weights: HeuristicWeights,
+}
+
+impl SnakeStrategy {
+    pub fn new() -> Self {
+        SnakeStrategy { weights: HeuristicWeights::snake() }
+    }
+}
+
+impl Default for SnakeStrategy {
+    fn default() -> Self {
+        SnakeStrategy::new()
+    }
+}
+
+impl Strategy for SnakeStrategy {
+    fn name(&self) -> String {
+        String::from("snake")
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        ai::best_move(game, &self.weights)
+    }
+}
+
+/// Looks one move ahead with any evaluator, such as a trained `NTupleNetwork`.
+pub struct EvaluatorStrategy {
+    name: String,
+    evaluator: Box<dyn Evaluator>,
+}
+
+impl EvaluatorStrategy {
+    pub fn new(name: &str, evaluator: Box<dyn Evaluator>) -> Self {
+        EvaluatorStrategy {
+            name: name.to_string(),
+            evaluator,
+        }
+    }
+}
+
+impl Strategy for EvaluatorStrategy {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        ai::best_move(game, self.evaluator.as_ref())
+    }
+}
+
+/// Searches `depth` moves ahead, maximizing over the player's moves and averaging over every
+/// possible spawn weighted by its probability. Leaves are scored by the evaluator.
+///
+/// Results for afterstates are cached in a transposition table keyed by the board's canonical
+/// form, so positions reached through different move orders or that are rotations/reflections of
+/// each other are only searched once.
+pub struct ExpectimaxStrategy {
+    depth: usize,
+    evaluator: Box<dyn Evaluator>,
+    table: Option<Mutex<TranspositionTable<f64>>>,
+}
+
+impl ExpectimaxStrategy {
+    /// Number of afterstates cached by default.
+    pub const DEFAULT_TABLE_CAPACITY: usize = 1 << 16;
+
+    /// Creates an expectimax search using the default `HeuristicWeights`.
+    pub fn new(depth: usize) -> Self {
+        ExpectimaxStrategy::with_evaluator(depth, Box::new(HeuristicWeights::default()))
+    }
+
+    pub fn with_evaluator(depth: usize, evaluator: Box<dyn Evaluator>) -> Self {
+        ExpectimaxStrategy {
+            depth: depth.max(1),
+            evaluator,
+            table: Some(Mutex::new(TranspositionTable::new(ExpectimaxStrategy::DEFAULT_TABLE_CAPACITY))),
+        }
+    }
+
+    /// Sets the number of cached afterstates. A capacity of 0 disables the transposition table.
+    pub fn table_capacity(mut self, capacity: usize) -> Self {
+        self.table = (capacity > 0).then(|| Mutex::new(TranspositionTable::new(capacity)));
+        self
+    }
+
+    /// Returns the `(hits, misses)` of the transposition table, if it is enabled.
+    pub fn table_stats(&self) -> Option<(u64, u64)> {
+        self.table.as_ref().map(|table| {
+            let table = table.lock().unwrap();
+            (table.hits(), table.misses())
+        })
+    }
+
+    /// Key for caching an afterstate. Symmetric positions share a key only if the evaluator is
+    /// guaranteed to treat them the same.
+    fn table_key(&self, afterstate: &Game) -> u128 {
+        let exponents = afterstate.exponents();
+
+        if self.evaluator.is_symmetric() {
+            transposition::canonical_key(&exponents)
+        } else {
+            transposition::pack(&exponents)
+        }
+    }
+
+    /// Value of the position with the player to move.
+    fn max_node(&self, game: &Game, depth: usize) -> f64 {
+        game.topology().directions().iter()
+            .filter_map(|&direction| game.afterstate(direction))
+            .map(|(afterstate, reward)| reward as f64 + self.chance_node(&afterstate, depth - 1))
+            .fold(None, |best: Option<f64>, value| Some(best.map_or(value, |best| best.max(value))))
+            .unwrap_or(GAME_OVER_VALUE)
+    }
+
+    /// Expected value of an afterstate over every possible spawn, looked up in the transposition
+    /// table when possible.
+    fn chance_node(&self, afterstate: &Game, depth: usize) -> f64 {
+        let Some(table) = &self.table else {
+            return self.search_chance_node(afterstate, depth)
+        };
+
+        let key = self.table_key(afterstate);
+
+        if let Some(value) = table.lock().unwrap().get(key, depth) {
+            return value
+        }
+
+        let value = self.search_chance_node(afterstate, depth);
+        table.lock().unwrap().insert(key, depth, value);
+
+        value
+    }
+
+    fn search_chance_node(&self, afterstate: &Game, depth: usize) -> f64 {
+        if depth == 0 {
+            return self.evaluator.evaluate(afterstate)
+        }
+
+        let empty_slots = afterstate.empty_slots();
+        let spawns: Vec<(u32, f64, usize, usize)> = afterstate.spawn_probabilities().into_iter()
+            .flat_map(|(value, probability)| empty_slots.iter().map(move |&(row, col)| (value, probability, row, col)))
+            .collect();
+
+        let values = parallel::map(&spawns, |&(value, probability, row, col)| {
+            let mut next = afterstate.clone();
+            next.place_tile(row, col, value);
+
+            probability * self.max_node(&next, depth)
+        });
+
+        values.iter().sum::<f64>() / empty_slots.len() as f64
+    }
+}
+
+impl Strategy for ExpectimaxStrategy {
+    fn name(&self) -> String {
+        format!("expectimax-{}", self.depth)
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        let candidates = afterstates(game);
+        let search = &*self;
+
+        let values = parallel::map(&candidates, |(_, afterstate, reward)| {
+            *reward as f64 + search.chance_node(afterstate, search.depth - 1)
+        });
+
+        best_candidate(&candidates, &values)
+    }
+}
+
+/// Plays `rollouts` random games after each candidate move and picks the move whose rollouts
+/// scored the most points on average.
+pub struct MonteCarloStrategy {
+    rollouts: usize,
+    max_rollout_moves: Option<usize>,
+    rng: StdRng,
+}
+
+impl MonteCarloStrategy {
+    pub fn new(rollouts: usize) -> Self {
+        MonteCarloStrategy {
+            rollouts: rollouts.max(1),
+            max_rollout_moves: None,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn with_seed(rollouts: usize, seed: u64) -> Self {
+        MonteCarloStrategy {
+            rng: StdRng::seed_from_u64(seed),
+            ..MonteCarloStrategy::new(rollouts)
+        }
+    }
+
+    /// Cuts rollouts short after the given number of moves, trading accuracy for speed.
+    pub fn max_rollout_moves(mut self, max_rollout_moves: usize) -> Self {
+        self.max_rollout_moves = Some(max_rollout_moves);
+        self
+    }
+
+    /// Plays random moves after a random spawn on `afterstate`, returning the points scored.
+    fn rollout(&self, afterstate: &Game, seed: u64) -> u32 {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // Rollouts must not see the spawns a seeded game is going to produce.
+        let mut game = afterstate.clone();
+        game.reseed(rng.gen());
+        game.spawn_tile();
+
+        let starting_score = game.score;
+        let mut moves = 0;
+        let mut directions = game.topology().directions().to_vec();
+
+        while self.max_rollout_moves.is_none_or(|max_moves| moves < max_moves) {
+            directions.shuffle(&mut rng);
+
+            let moved = directions.iter()
+                .any(|&direction| matches!(game.make_move(direction), InputResult::Ok(_, _, _)));
+
+            if !moved {
+                break
+            }
+
+            moves += 1;
+        }
+
+        game.score - starting_score
+    }
+}
+
+impl Strategy for MonteCarloStrategy {
+    fn name(&self) -> String {
+        format!("montecarlo-{}", self.rollouts)
+    }
+
+    fn choose(&mut self, game: &Game) -> Option<Direction> {
+        let candidates = afterstates(game);
+
+        // Rollout seeds are drawn up front so that results do not depend on how rollouts are
+        // scheduled across threads.
+        let mut rollouts = Vec::with_capacity(candidates.len() * self.rollouts);
+
+        for candidate in 0..candidates.len() {
+            for _ in 0..self.rollouts {
+                rollouts.push((candidate, self.rng.gen::<u64>()));
+            }
+        }
+
+        let search = &*self;
+        let scores = parallel::map(&rollouts, |&(candidate, seed)| search.rollout(&candidates[candidate].1, seed));
+
+        let values: Vec<f64> = candidates.iter().enumerate()
+            .map(|(candidate, (_, _, reward))| {
+                let total: u64 = rollouts.iter().zip(&scores)
+                    .filter(|((rollout_candidate, _), _)| *rollout_candidate == candidate)
+                    .map(|(_, &score)| score as u64)
+                    .sum();
+
+                *reward as f64 + total as f64 / self.rollouts as f64
+            })
+            .collect();
+
+        best_candidate(&candidates, &values)
+    }
+}
+
+// Helper functions
+
+/// Every valid move along with its afterstate and the points it scores.
+fn afterstates(game: &Game) -> Vec<(Direction, Game, u32)> {
+    game.topology().directions().iter()
+        .filter_map(|&direction| game.afterstate(direction).map(|(afterstate, reward)| (direction, afterstate, reward)))
+        .collect()
+}
+
+/// Returns the direction with the highest value. Ties go to the earliest candidate.
+fn best_candidate(candidates: &[(Direction, Game, u32)], values: &[f64]) -> Option<Direction> {
+    let mut best: Option<(f64, Direction)> = None;
+
+    for ((direction, _, _), &value) in candidates.iter().zip(values) {
+        if best.is_none_or(|(best_value, _)| value > best_value) {
+            best = Some((value, *direction));
+        }
+    }
+
+    best.map(|(_, direction)| direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExponentGrid, Topology};
+
+    /// Plays until the game ends or `max_moves` is reached, returning the number of moves made.
+    fn play(strategy: &mut dyn Strategy, game: &mut Game, max_moves: usize) -> usize {
+        let mut moves = 0;
+
+        while moves < max_moves {
+            match strategy.choose(game) {
+                Some(direction) => {
+                    assert!(matches!(game.make_move(direction), InputResult::Ok(_, _, _)),
+                            "{} chose an invalid move.", strategy.name());
+                    moves += 1;
+                },
+                None => break,
+            }
+        }
+
+        moves
+    }
+
+    #[test]
+    /// Every built-in strategy should only ever choose valid moves.
+    fn test_strategies_choose_valid_moves() {
+        for name in STRATEGY_NAMES {
+            let mut strategy = by_name(name).unwrap();
+            let mut game = Game::new();
+
+            assert!(play(strategy.as_mut(), &mut game, 5) > 0);
+        }
+    }
+
+    #[test]
+    /// Strategies must return `None` once the board is stuck.
+    fn test_no_move_on_stuck_board() {
+        let board: ExponentGrid = [[1, 2, 1, 2], [2, 1, 2, 1], [1, 2, 1, 2], [2, 1, 2, 1]];
+        let game = Game::from_exponents(&board);
+
+        for name in STRATEGY_NAMES {
+            assert_eq!(by_name(name).unwrap().choose(&game), None);
+        }
+    }
+
+    #[test]
+    /// Strategies on hex boards must also consider the diagonal moves.
+    fn test_hex_diagonal_moves() {
+        // Only the diagonals merge anything here, square moves are all blocked.
+        let board: ExponentGrid = [[2, 1, 2, 1], [1, 4, 5, 6], [2, 3, 7, 4], [1, 6, 4, 2]];
+        let mut game = Game::from_exponents(&board);
+        game.set_topology(Topology::Hex);
+        assert!(!game.game_over());
+
+        for name in STRATEGY_NAMES {
+            let direction = by_name(name).unwrap().choose(&game);
+            assert!(matches!(direction, Some(Direction::UpRight | Direction::DownLeft)), "{} chose {:?}", name, direction);
+        }
+    }
+
+    /// Default heuristic that opts out of sharing results between symmetric positions.
+    struct AsymmetricHeuristic;
+
+    impl Evaluator for AsymmetricHeuristic {
+        fn evaluate(&self, game: &Game) -> f64 {
+            HeuristicWeights::default().evaluate(game)
+        }
+
+        fn is_symmetric(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    /// Cached results must be identical to searching from scratch. Only exact positions are shared
+    /// here, since symmetric positions may differ by floating point rounding and flip near ties.
+    fn test_expectimax_transposition_table() {
+        let mut cached = ExpectimaxStrategy::with_evaluator(2, Box::new(AsymmetricHeuristic));
+        let mut uncached = ExpectimaxStrategy::with_evaluator(2, Box::new(AsymmetricHeuristic)).table_capacity(0);
+        let mut symmetric = ExpectimaxStrategy::new(2);
+        let mut game = Game::with_seed(5);
+
+        for _ in 0..10 {
+            let direction = cached.choose(&game);
+            assert_eq!(direction, uncached.choose(&game));
+            assert!(symmetric.choose(&game).is_some());
+
+            game.make_move(direction.unwrap());
+        }
+
+        let (hits, _) = cached.table_stats().unwrap();
+        let (symmetric_hits, _) = symmetric.table_stats().unwrap();
+
+        assert!(hits > 0);
+        assert!(symmetric_hits > 0);
+        assert_eq!(uncached.table_stats(), None);
+    }
+
+    #[test]
+    fn test_greedy_takes_largest_merge() {
+        // Sliding left or right merges the 8-tiles; up or down only merges the 2-tiles.
+        let board: ExponentGrid = [[3, 3, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0], [0, 0, 0, 0]];
+        let game = Game::from_exponents(&board);
+
+        assert_eq!(GreedyStrategy.choose(&game), Some(Direction::Left));
+    }
+
+    #[test]
+    fn test_corner_priority() {
+        // Nothing can move down, so the corner strategy falls back to left.
+        let board: ExponentGrid = [[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 1, 0, 2]];
+        let game = Game::from_exponents(&board);
+
+        assert_eq!(CornerStrategy::new().choose(&game), Some(Direction::Left));
+    }
+}
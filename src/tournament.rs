@@ -0,0 +1,262 @@
+//! Plays several strategies on the same list of seeds and summarizes the results.
+//!
+//! Because every game is created with `Game::with_seed()`, each strategy sees exactly the same
+//! spawn sequence for a given seed as long as it makes the same moves.
+
+use instant::Instant;
+use serde::Serialize;
+use std::fmt::Write;
+
+use crate::strategy::Strategy;
+use crate::{parallel, Game, InputResult};
+
+/// Builds a fresh strategy for every game, so that games are independent of each other and can
+/// be played on separate threads.
+pub type StrategyFactory<'a> = &'a (dyn Fn() -> Box<dyn Strategy> + Sync);
+
+/// Result of a single game.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub strategy: String,
+    pub seed: u64,
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: usize,
+    pub micros_per_move: f64,
+}
+
+/// Fraction of a strategy's games that reached `goal`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct WinRate {
+    pub goal: u32,
+    pub rate: f64,
+}
+
+/// Aggregate results for one strategy.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct StrategySummary {
+    pub strategy: String,
+    pub games: usize,
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub best_score: u32,
+    pub mean_max_tile: f64,
+    pub best_max_tile: u32,
+    pub mean_moves: f64,
+    pub micros_per_move: f64,
+    pub win_rates: Vec<WinRate>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TournamentReport {
+    pub seeds: Vec<u64>,
+    pub goals: Vec<u32>,
+    pub games: Vec<GameRecord>,
+    pub summaries: Vec<StrategySummary>,
+}
+
+/// Plays a complete game with the given seed. Stops early if `max_moves` is reached.
+pub fn play_game(strategy: &mut dyn Strategy, seed: u64, max_moves: Option<usize>) -> GameRecord {
+    let mut game = Game::with_seed(seed);
+    let mut moves = 0;
+    let start = Instant::now();
+
+    while max_moves.is_none_or(|max_moves| moves < max_moves) {
+        let Some(direction) = strategy.choose(&game) else {
+            break
+        };
+
+        match game.make_move(direction) {
+            InputResult::Ok(_, _, _) => moves += 1,
+            // A strategy choosing an invalid move forfeits the rest of the game.
+            InputResult::Err(_) => break,
+        }
+    }
+
+    let elapsed = start.elapsed().as_secs_f64() * 1_000_000.0;
+
+    GameRecord {
+        strategy: strategy.name(),
+        seed,
+        score: game.score,
+        max_tile: game.max_tile(),
+        moves,
+        micros_per_move: if moves > 0 { elapsed / moves as f64 } else { 0.0 },
+    }
+}
+
+/// Plays one game per seed, each with a fresh strategy from `make_strategy`. Games are played
+/// across threads when the `parallel` feature is enabled; records are returned in seed order.
+pub fn play_games(make_strategy: StrategyFactory, seeds: &[u64], max_moves: Option<usize>) -> Vec<GameRecord> {
+    parallel::map(seeds, |&seed| play_game(make_strategy().as_mut(), seed, max_moves))
+}
+
+/// Plays every strategy on every seed. `on_game` is called for each game once a strategy has
+/// finished all of its seeds, which the command line tool uses to report progress.
+pub fn run(strategies: &[StrategyFactory],
+           seeds: &[u64],
+           goals: &[u32],
+           max_moves: Option<usize>,
+           mut on_game: impl FnMut(&GameRecord)) -> TournamentReport {
+    let mut games = Vec::with_capacity(strategies.len() * seeds.len());
+    let mut summaries = Vec::with_capacity(strategies.len());
+
+    for make_strategy in strategies {
+        let records = play_games(*make_strategy, seeds, max_moves);
+        records.iter().for_each(&mut on_game);
+
+        summaries.push(summarize(&make_strategy().name(), &records, goals));
+        games.extend(records);
+    }
+
+    TournamentReport {
+        seeds: seeds.to_vec(),
+        goals: goals.to_vec(),
+        games,
+        summaries,
+    }
+}
+
+/// Aggregates the games played by one strategy.
+pub fn summarize(strategy: &str, records: &[GameRecord], goals: &[u32]) -> StrategySummary {
+    let count = records.len().max(1) as f64;
+
+    let mut scores: Vec<u32> = records.iter().map(|record| record.score).collect();
+    scores.sort_unstable();
+
+    let median_score = match scores.len() {
+        0 => 0.0,
+        len if len % 2 == 0 => (scores[len / 2 - 1] as f64 + scores[len / 2] as f64) / 2.0,
+        len => scores[len / 2] as f64,
+    };
+
+    let total_moves: usize = records.iter().map(|record| record.moves).sum();
+    let total_micros: f64 = records.iter().map(|record| record.micros_per_move * record.moves as f64).sum();
+
+    let win_rates = goals.iter()
+        .map(|&goal| WinRate {
+            goal,
+            rate: records.iter().filter(|record| record.max_tile >= goal).count() as f64 / count,
+        })
+        .collect();
+
+    StrategySummary {
+        strategy: strategy.to_string(),
+        games: records.len(),
+        mean_score: scores.iter().map(|&score| score as f64).sum::<f64>() / count,
+        median_score,
+        best_score: scores.last().copied().unwrap_or(0),
+        mean_max_tile: records.iter().map(|record| record.max_tile as f64).sum::<f64>() / count,
+        best_max_tile: records.iter().map(|record| record.max_tile).max().unwrap_or(0),
+        mean_moves: total_moves as f64 / count,
+        micros_per_move: if total_moves > 0 { total_micros / total_moves as f64 } else { 0.0 },
+        win_rates,
+    }
+}
+
+impl TournamentReport {
+    /// One CSV row per game.
+    pub fn games_csv(&self) -> String {
+        let mut csv = String::from("strategy,seed,score,max_tile,moves,micros_per_move\n");
+
+        for game in &self.games {
+            writeln!(csv, "{},{},{},{},{},{:.1}",
+                     csv_field(&game.strategy),
+                     game.seed,
+                     game.score,
+                     game.max_tile,
+                     game.moves,
+                     game.micros_per_move).unwrap();
+        }
+
+        csv
+    }
+
+    /// One CSV row per strategy, with a `win_rate_<goal>` column for every goal tile.
+    pub fn summary_csv(&self) -> String {
+        let mut csv = String::from("strategy,games,mean_score,median_score,best_score,mean_max_tile,best_max_tile,mean_moves,micros_per_move");
+
+        for goal in &self.goals {
+            write!(csv, ",win_rate_{}", goal).unwrap();
+        }
+
+        csv.push('\n');
+
+        for summary in &self.summaries {
+            write!(csv, "{},{},{:.1},{:.1},{},{:.1},{},{:.1},{:.1}",
+                   csv_field(&summary.strategy),
+                   summary.games,
+                   summary.mean_score,
+                   summary.median_score,
+                   summary.best_score,
+                   summary.mean_max_tile,
+                   summary.best_max_tile,
+                   summary.mean_moves,
+                   summary.micros_per_move).unwrap();
+
+            for win_rate in &summary.win_rates {
+                write!(csv, ",{:.4}", win_rate.rate).unwrap();
+            }
+
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Tournament reports always serialize.")
+    }
+}
+
+// Helper functions
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{CornerStrategy, GreedyStrategy};
+
+    #[test]
+    /// Playing the same deterministic strategy on the same seed must give identical games.
+    fn test_same_seed_same_game() {
+        let first = play_game(&mut GreedyStrategy, 42, None);
+        let second = play_game(&mut GreedyStrategy, 42, None);
+
+        assert_eq!((first.score, first.max_tile, first.moves), (second.score, second.max_tile, second.moves));
+    }
+
+    #[test]
+    fn test_report() {
+        let greedy = || -> Box<dyn Strategy> { Box::new(GreedyStrategy) };
+        let corner = || -> Box<dyn Strategy> { Box::new(CornerStrategy::new()) };
+        let mut played = 0;
+
+        let report = run(&[&greedy, &corner], &[1, 2, 3], &[64, 1 << 20], Some(200), |_| played += 1);
+
+        assert_eq!(played, 6);
+        assert_eq!(report.games.len(), 6);
+        assert_eq!(report.summaries.len(), 2);
+
+        for summary in &report.summaries {
+            assert_eq!(summary.games, 3);
+            assert_eq!(summary.win_rates.len(), 2);
+            assert_eq!(summary.win_rates[1].rate, 0.0);
+        }
+
+        assert_eq!(report.games_csv().lines().count(), 7);
+        assert!(report.summary_csv().starts_with("strategy,games,"));
+        assert!(report.summary_csv().lines().next().unwrap().ends_with("win_rate_64,win_rate_1048576"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["games"].as_array().unwrap().len(), 6);
+    }
+}
@@ -0,0 +1,203 @@
+//! Board symmetry canonicalization and a bounded transposition table keyed by it.
+//!
+//! The 8 rotations/reflections of a board are strategically identical, so searches and datasets
+//! can treat them as one position by always working with the canonical form: whichever of the 8
+//! orientations packs into the smallest key.
+
+use std::collections::HashSet;
+
+use crate::{transform_coordinates, ExponentGrid, BOARD_DIMENSION};
+
+pub const NUM_SYMMETRIES: usize = 8;
+
+/// Bits used for each cell when packing a board, enough for tiles up to 2^31.
+const BITS_PER_CELL: usize = 5;
+
+/// Returns the board as seen under one of the 8 symmetries. Symmetries 0-3 are clockwise
+/// rotations by 0, 90, 180 and 270 degrees; 4-7 are the same rotations after a horizontal
+/// reflection.
+pub fn transform(exponents: &ExponentGrid, symmetry: usize) -> ExponentGrid {
+    let mut transformed = [[0; BOARD_DIMENSION]; BOARD_DIMENSION];
+
+    for (row, cells) in exponents.iter().enumerate() {
+        for (col, &exponent) in cells.iter().enumerate() {
+            let (new_row, new_col) = transform_coordinates(row, col, symmetry);
+            transformed[new_row][new_col] = exponent;
+        }
+    }
+
+    transformed
+}
+
+/// Packs a board into an exact, collision-free key, 5 bits per cell in row-major order.
+pub fn pack(exponents: &ExponentGrid) -> u128 {
+    exponents.iter().flatten().fold(0, |key, &exponent| (key << BITS_PER_CELL) | exponent as u128)
+}
+
+/// Key of the canonical form of the board. Equal for all 8 symmetries of a board.
+pub fn canonical_key(exponents: &ExponentGrid) -> u128 {
+    (0..NUM_SYMMETRIES).map(|symmetry| pack(&transform(exponents, symmetry))).min().unwrap()
+}
+
+/// Returns whichever of the board's 8 symmetries has the smallest key.
+pub fn canonical_form(exponents: &ExponentGrid) -> ExponentGrid {
+    (0..NUM_SYMMETRIES)
+        .map(|symmetry| transform(exponents, symmetry))
+        .min_by_key(pack)
+        .unwrap()
+}
+
+/// Fast 64-bit hash of a key, suitable for indexing tables.
+pub fn hash_key(key: u128) -> u64 {
+    let mut z = (key as u64) ^ ((key >> 64) as u64).rotate_left(32);
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Returns the canonical form of every distinct position, keeping the order in which each was first
+/// seen. Positions that are rotations/reflections of each other count as the same position.
+pub fn deduplicate(positions: impl IntoIterator<Item = ExponentGrid>) -> Vec<ExponentGrid> {
+    let mut seen = HashSet::new();
+
+    positions.into_iter()
+        .map(|position| canonical_form(&position))
+        .filter(|canonical| seen.insert(pack(canonical)))
+        .collect()
+}
+
+struct Entry<V> {
+    key: u128,
+    depth: usize,
+    value: V,
+}
+
+/// Fixed-size cache of search results. Each key maps to a single slot, and newer results simply
+/// replace whatever occupied it, so memory use never grows beyond the initial capacity.
+pub struct TranspositionTable<V> {
+    entries: Vec<Option<Entry<V>>>,
+    len: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl<V: Copy> TranspositionTable<V> {
+    pub fn new(capacity: usize) -> Self {
+        TranspositionTable {
+            entries: (0..capacity.max(1)).map(|_| None).collect(),
+            len: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the value stored for `key` if it was searched to exactly `depth`.
+    pub fn get(&mut self, key: u128, depth: usize) -> Option<V> {
+        let slot = self.slot(key);
+
+        match &self.entries[slot] {
+            Some(entry) if entry.key == key && entry.depth == depth => {
+                self.hits += 1;
+                Some(entry.value)
+            },
+            _ => {
+                self.misses += 1;
+                None
+            },
+        }
+    }
+
+    pub fn insert(&mut self, key: u128, depth: usize, value: V) {
+        let slot = self.slot(key);
+
+        if self.entries[slot].is_none() {
+            self.len += 1;
+        }
+
+        self.entries[slot] = Some(Entry { key, depth, value });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None);
+        self.len = 0;
+    }
+
+    /// Number of occupied slots.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    fn slot(&self, key: u128) -> usize {
+        (hash_key(key) % self.entries.len() as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: ExponentGrid = [[1, 2, 0, 0], [0, 3, 0, 0], [0, 0, 0, 4], [5, 0, 0, 17]];
+
+    #[test]
+    /// All 8 symmetries of a board should share one canonical form, and the symmetries of an
+    /// asymmetric board should all be distinct.
+    fn test_canonical_form() {
+        let canonical = canonical_form(&BOARD);
+        let mut keys = HashSet::new();
+
+        for symmetry in 0..NUM_SYMMETRIES {
+            let transformed = transform(&BOARD, symmetry);
+
+            assert_eq!(canonical_form(&transformed), canonical);
+            assert_eq!(canonical_key(&transformed), pack(&canonical));
+            keys.insert(pack(&transformed));
+        }
+
+        assert_eq!(keys.len(), NUM_SYMMETRIES);
+
+        let mut other = BOARD;
+        other[1][1] = 2;
+        assert_ne!(canonical_key(&other), canonical_key(&BOARD));
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let positions = vec![BOARD, transform(&BOARD, 3), [[1; BOARD_DIMENSION]; BOARD_DIMENSION], transform(&BOARD, 6)];
+        assert_eq!(deduplicate(positions).len(), 2);
+    }
+
+    #[test]
+    fn test_table_is_bounded() {
+        let mut table = TranspositionTable::new(16);
+
+        for key in 0..1000u128 {
+            table.insert(key, 1, key as f64);
+        }
+
+        assert!(table.len() <= 16);
+        assert_eq!(table.get(999, 1), Some(999.0));
+        assert_eq!(table.get(999, 2), None);
+        assert_eq!((table.hits(), table.misses()), (1, 1));
+
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.get(999, 1), None);
+    }
+}
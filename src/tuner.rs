@@ -0,0 +1,274 @@
+//! Genetic algorithm that tunes `HeuristicWeights` over seeded batches of simulated games.
+//!
+//! Every random choice is derived from the configured seed and the generation number, so a run
+//! produces the same weights whether it ran uninterrupted or was resumed from a checkpoint.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+use crate::ai::HeuristicWeights;
+use crate::rng::SeededRng;
+use crate::strategy::{EvaluatorStrategy, Strategy};
+use crate::{parallel, tournament};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TunerConfig {
+    /// Number of weight sets evaluated per generation.
+    pub population: usize,
+    /// Number of best weight sets carried over unchanged to the next generation.
+    pub elites: usize,
+    /// Games played by every weight set in each generation.
+    pub games_per_evaluation: usize,
+    /// Standard deviation of the multiplicative noise applied by mutation.
+    pub mutation_strength: f64,
+    /// Cuts games short to keep generations fast. `None` plays every game to the end.
+    pub max_moves: Option<usize>,
+    pub seed: u64,
+}
+
+impl Default for TunerConfig {
+    fn default() -> Self {
+        TunerConfig {
+            population: 16,
+            elites: 4,
+            games_per_evaluation: 8,
+            mutation_strength: 0.3,
+            max_moves: None,
+            seed: 1,
+        }
+    }
+}
+
+/// Everything needed to resume tuning.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub config: TunerConfig,
+    /// Number of generations evaluated so far.
+    pub generation: usize,
+    pub population: Vec<HeuristicWeights>,
+    pub best: HeuristicWeights,
+    pub best_fitness: f64,
+}
+
+impl Checkpoint {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).expect("Checkpoints always serialize.");
+        std::fs::write(path, contents)
+    }
+}
+
+/// Results of a single generation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    pub best: HeuristicWeights,
+}
+
+pub struct Tuner {
+    checkpoint: Checkpoint,
+}
+
+impl Tuner {
+    /// Starts a new run with a population scattered around the default weights.
+    pub fn new(config: TunerConfig) -> Self {
+        let mut rng = SeededRng::new(config.seed);
+        let default = HeuristicWeights::default();
+
+        let mut population = vec![default];
+
+        while population.len() < config.population.max(2) {
+            population.push(mutate(&default, config.mutation_strength, &mut rng));
+        }
+
+        Tuner {
+            checkpoint: Checkpoint {
+                config,
+                generation: 0,
+                population,
+                best: default,
+                best_fitness: f64::NEG_INFINITY,
+            },
+        }
+    }
+
+    pub fn from_checkpoint(checkpoint: Checkpoint) -> Self {
+        Tuner { checkpoint }
+    }
+
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    /// Weights with the highest fitness seen so far.
+    pub fn best(&self) -> HeuristicWeights {
+        self.checkpoint.best
+    }
+
+    /// Evaluates the current population and breeds the next one.
+    pub fn step(&mut self) -> GenerationStats {
+        let checkpoint = &mut self.checkpoint;
+        let config = checkpoint.config.clone();
+        let generation = checkpoint.generation;
+
+        // All weight sets in a generation play the same seeds so their fitness is comparable.
+        let seeds: Vec<u64> = (0..config.games_per_evaluation as u64)
+            .map(|game| derive_seed(config.seed, generation as u64, game))
+            .collect();
+
+        let mut ranked: Vec<(f64, HeuristicWeights)> = parallel::map(&checkpoint.population, |weights| {
+            (fitness(weights, &seeds, config.max_moves), *weights)
+        });
+
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let (best_fitness, best) = ranked[0];
+        let mean_fitness = ranked.iter().map(|(fitness, _)| fitness).sum::<f64>() / ranked.len() as f64;
+
+        if best_fitness > checkpoint.best_fitness {
+            checkpoint.best_fitness = best_fitness;
+            checkpoint.best = best;
+        }
+
+        let mut rng = SeededRng::new(derive_seed(config.seed, generation as u64, u64::MAX));
+        let elites = config.elites.clamp(1, ranked.len());
+        let mut population: Vec<HeuristicWeights> = ranked[..elites].iter().map(|(_, weights)| *weights).collect();
+
+        while population.len() < ranked.len() {
+            let first = tournament_select(&ranked, &mut rng);
+            let second = tournament_select(&ranked, &mut rng);
+            let child = crossover(&first, &second, &mut rng);
+
+            population.push(mutate(&child, config.mutation_strength, &mut rng));
+        }
+
+        checkpoint.population = population;
+        checkpoint.generation += 1;
+
+        GenerationStats {
+            generation,
+            best_fitness,
+            mean_fitness,
+            best,
+        }
+    }
+}
+
+/// Mean score of a one-move-lookahead player using `weights` over the given seeds.
+pub fn fitness(weights: &HeuristicWeights, seeds: &[u64], max_moves: Option<usize>) -> f64 {
+    let make_strategy = || -> Box<dyn Strategy> { Box::new(EvaluatorStrategy::new("tuned", Box::new(*weights))) };
+    let total: u64 = tournament::play_games(&make_strategy, seeds, max_moves).iter()
+        .map(|record| record.score as u64)
+        .sum();
+
+    total as f64 / seeds.len().max(1) as f64
+}
+
+// Helper functions
+
+/// Mixes the run seed, generation and index into an independent seed (SplitMix64 finalizer).
+fn derive_seed(seed: u64, generation: u64, index: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add(generation.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add(index.wrapping_mul(0xD1B5_4A32_D192_ED03));
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Picks the fitter of two random individuals.
+fn tournament_select(ranked: &[(f64, HeuristicWeights)], rng: &mut SeededRng) -> HeuristicWeights {
+    let first = rng.gen_range(0..ranked.len());
+    let second = rng.gen_range(0..ranked.len());
+
+    // `ranked` is sorted best first.
+    ranked[first.min(second)].1
+}
+
+/// Takes each weight from either parent with equal probability.
+fn crossover(first: &HeuristicWeights, second: &HeuristicWeights, rng: &mut SeededRng) -> HeuristicWeights {
+    let first = first.to_array();
+    let second = second.to_array();
+
+    HeuristicWeights::from_array(std::array::from_fn(|i| if rng.gen() { first[i] } else { second[i] }))
+}
+
+/// Scales every weight by `exp(strength * N(0, 1))`, and occasionally flips a weight's sign so the
+/// search can discover features that are better penalized than rewarded.
+fn mutate(weights: &HeuristicWeights, strength: f64, rng: &mut SeededRng) -> HeuristicWeights {
+    let mut weights = weights.to_array();
+
+    for weight in weights.iter_mut() {
+        *weight *= (strength * standard_normal(rng)).exp();
+
+        if rng.gen_bool(0.02) {
+            *weight = -*weight;
+        }
+    }
+
+    HeuristicWeights::from_array(weights)
+}
+
+/// Samples a standard normal distribution with the Box-Muller transform.
+fn standard_normal(rng: &mut SeededRng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_config() -> TunerConfig {
+        TunerConfig {
+            population: 4,
+            elites: 1,
+            games_per_evaluation: 2,
+            mutation_strength: 0.3,
+            max_moves: Some(30),
+            seed: 7,
+        }
+    }
+
+    #[test]
+    /// A resumed run must produce exactly the same result as an uninterrupted one.
+    fn test_resume_is_reproducible() {
+        let mut uninterrupted = Tuner::new(small_config());
+        uninterrupted.step();
+        uninterrupted.step();
+
+        let mut first_half = Tuner::new(small_config());
+        first_half.step();
+
+        let path = std::env::temp_dir().join(format!("tuner_checkpoint_{}.json", std::process::id()));
+        first_half.checkpoint().save(&path).unwrap();
+        let mut resumed = Tuner::from_checkpoint(Checkpoint::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        resumed.step();
+
+        assert_eq!(resumed.checkpoint(), uninterrupted.checkpoint());
+    }
+
+    #[test]
+    fn test_weights_round_trip() {
+        let weights = HeuristicWeights::snake();
+        assert_eq!(HeuristicWeights::from_array(weights.to_array()), weights);
+
+        let path = std::env::temp_dir().join(format!("heuristic_weights_{}.json", std::process::id()));
+        weights.save(&path).unwrap();
+        assert_eq!(HeuristicWeights::load(&path).unwrap(), weights);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+use rust_2048::spawn::RandomSpawner;
+use rust_2048::{Edges, Game, InputResult, MergeRule, Topology, BOARD_DIMENSION};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+use crate::*;
+
+const STATUS_SELECTOR: &str = ".variant-status";
+
+/// Single-player games played under rules other than the classic ones.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Variant {
+    /// A board of hexagonal cells, see `rust_2048::Topology::Hex`.
+    Hex,
+    /// A square board whose edges wrap around, see `rust_2048::Edges::Wrapped`.
+    Wrap,
+    /// Tiles of consecutive Fibonacci numbers merge, see `rust_2048::MergeRule::Fibonacci`.
+    Fibonacci,
+    /// Three equal powers of three merge, see `rust_2048::MergeRule::Tripling`.
+    Triples,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 4] = [Variant::Hex, Variant::Wrap, Variant::Fibonacci, Variant::Triples];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Hex => "Hex",
+            Variant::Wrap => "Wrap",
+            Variant::Fibonacci => "Fibonacci",
+            Variant::Triples => "Triples",
+        }
+    }
+
+    fn new_game(self) -> Game {
+        let mut game = self.deal();
+
+        // Lone tiles cannot move on wrapped boards, so an opening whose two tiles share no line is
+        // already over. Those are dealt again.
+        while game.game_over() {
+            game = self.deal();
+        }
+
+        game
+    }
+
+    fn deal(self) -> Game {
+        let mut game = match self {
+            Variant::Fibonacci => Game::with_merge_rule(Box::new(RandomSpawner), MergeRule::Fibonacci),
+            Variant::Triples => Game::with_merge_rule(Box::new(RandomSpawner), MergeRule::Tripling),
+            _ => Game::new(),
+        };
+
+        match self {
+            Variant::Hex => game.set_topology(Topology::Hex),
+            Variant::Wrap => game.set_edges(Edges::Wrapped),
+            Variant::Fibonacci | Variant::Triples => (),
+        }
+
+        game
+    }
+
+    /// Class of the board container, which decides how the tiles are laid out and clipped.
+    fn board_class(self) -> &'static str {
+        match self {
+            Variant::Hex => "board-container hex",
+            Variant::Wrap => "board-container wrap",
+            Variant::Fibonacci | Variant::Triples => "board-container",
+        }
+    }
+
+    fn controls(self) -> &'static str {
+        match self {
+            Variant::Hex => "Q W E slide up-left, up and up-right; A S D down-left, down and down-right.",
+            Variant::Wrap => "Tiles sliding past an edge come back in from the opposite one.",
+            Variant::Fibonacci => "Consecutive Fibonacci numbers merge: 1 + 1, 1 + 2, 2 + 3, 3 + 5 and so on.",
+            Variant::Triples => "Three equal tiles in a line merge into one of three times their value.",
+        }
+    }
+}
+
+pub enum VariantCommand {
+    /// `KeyboardEvent.code` of a key pressed anywhere on the page.
+    Key(String),
+    /// Deals a new board, replacing the game in progress.
+    NewGame,
+    /// Sent when the variant screen is unmounted.
+    Close,
+}
+
+async fn process_variant_commands(variant: Variant, mut command_rx: UnboundedReceiver<VariantCommand>, mut animationend_rx: counted_channel::CountedReceiver) {
+    let mut game = variant.new_game();
+
+    while let Some(command) = command_rx.recv().await {
+        match command {
+            VariantCommand::Key(code) => {
+                // Games that are over take no more moves; `game_over()` checks every direction of
+                // the board.
+                if game.game_over() {
+                    continue
+                }
+
+                let direction = game.topology().direction_for_key(&code);
+                let edges = game.edges();
+
+                if let InputResult::Ok(new_tile_id, tiles, game_won) = game.receive_input(&code) {
+                    match (edges, direction) {
+                        (Edges::Wrapped, Some(direction)) => animate_wrapped_move(&board_container(), &tiles, new_tile_id, direction, &mut animationend_rx).await,
+                        _ => animate_move(&board_container(), &tiles, new_tile_id, &mut animationend_rx).await,
+                    }
+                    update_score(game.score);
+
+                    if game_won {
+                        set_status(STATUS_SELECTOR, &format!("You reached {}! Keep going for a higher score.", game.config().goal_tile));
+                    }
+                }
+
+                if game.game_over() {
+                    set_status(STATUS_SELECTOR, &format!("No moves left. Final score {}.", game.score));
+                }
+            },
+            VariantCommand::NewGame => {
+                game = variant.new_game();
+                render_board(&board_container(), &game);
+                update_score(0);
+                set_status(STATUS_SELECTOR, "");
+            },
+            VariantCommand::Close => break,
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct VariantGameProps {
+    pub variant: Variant,
+    pub close_callback: Callback<MouseEvent>,
+}
+
+/// A single-player game under the rules of one of the variants.
+#[function_component(VariantGame)]
+pub fn variant_game(props: &VariantGameProps) -> Html {
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let (animationend_tx, animationend_rx) = counted_channel::CountedChannel::new();
+
+    spawn_local(process_variant_commands(props.variant, command_rx, animationend_rx));
+
+    {
+        let command_tx = command_tx.clone();
+
+        use_effect(move || {
+            // `NewGame` renders into the board, so it is only sent once the board is mounted.
+            command_tx.send(VariantCommand::NewGame).unwrap();
+
+            let key_tx = command_tx.clone();
+            let remove_listeners = add_game_listeners(Some(Callback::from(move |code| key_tx.send(VariantCommand::Key(code)).unwrap())), animationend_tx);
+
+            move || {
+                remove_listeners();
+                command_tx.send(VariantCommand::Close).unwrap();
+            }
+        });
+    }
+
+    let new_game_callback = Callback::from(move |_| command_tx.send(VariantCommand::NewGame).unwrap());
+
+    let style_args = button_style_args();
+    let status_style = format!("--status_text: {}", COLORS.text_light);
+    let board = match props.variant {
+        Variant::Hex => html! { <HexBoard/> },
+        _ => html! { <GameBoard/> },
+    };
+
+    html! {
+        <div class="content variant-game noselect">
+            <div class="metadata-container">
+                <Score score={0}/>
+                <NewGameButton onclick={props.close_callback.clone()} button_text={"Back to Game"} disabled={false}/>
+            </div>
+            <div class={props.variant.board_class()}>
+                { board }
+            </div>
+            <div class="variant-controls" style={status_style.clone()}>
+                <span>{ props.variant.controls() }</span>
+                <button class="metadata" onclick={new_game_callback} style={style_args}>{ "New Game" }</button>
+            </div>
+            <div class="variant-status" style={status_style}/>
+        </div>
+    }
+}
+
+/// The empty cells of a hex board, placed where `convert_hex_to_pixels()` puts its tiles.
+#[function_component(HexBoard)]
+fn hex_board() -> Html {
+    let cell_style = format!("--cell_background: {};", COLORS.cell);
+
+    html! {
+        <>
+            { for (0..BOARD_DIMENSION).flat_map(|row| (0..BOARD_DIMENSION).map(move |col| (row, col))).map(|(row, col)| {
+                let (top_offset, left_offset) = convert_hex_to_pixels(row, col);
+
+                html! {
+                    <div class="hex-cell" style={format!("{} top: {}px; left: {}px;", cell_style, top_offset, left_offset)}/>
+                }
+            })}
+        </>
+    }
+}
@@ -0,0 +1,181 @@
+//! Rules of split-screen races, in which two players play their own boards from the same seed.
+//!
+//! The first player to reach the goal tile wins. If neither does, the race is decided by score once
+//! both players are out of moves.
+
+use std::cmp::Ordering;
+
+use crate::{Direction, Game, InputResult, InvalidMove};
+
+pub const PLAYERS: usize = 2;
+
+/// Keys a player steers with. Single-player games accept all of them, see `Direction::from_key()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Controls {
+    Wasd,
+    Arrows,
+}
+
+impl Controls {
+    /// Controls of each player, by player index.
+    pub const ALL: [Controls; PLAYERS] = [Controls::Wasd, Controls::Arrows];
+
+    /// Maps a `KeyboardEvent.code` to a direction if it is one of these controls.
+    pub fn direction(self, key: &str) -> Option<Direction> {
+        match (self, key) {
+            (Controls::Wasd, "KeyW") | (Controls::Arrows, "ArrowUp") => Some(Direction::Up),
+            (Controls::Wasd, "KeyS") | (Controls::Arrows, "ArrowDown") => Some(Direction::Down),
+            (Controls::Wasd, "KeyA") | (Controls::Arrows, "ArrowLeft") => Some(Direction::Left),
+            (Controls::Wasd, "KeyD") | (Controls::Arrows, "ArrowRight") => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Controls::Wasd => "WASD",
+            Controls::Arrows => "Arrows",
+        }
+    }
+}
+
+/// How a race was decided. Players are given by index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The player reached the goal tile first.
+    Reached(usize),
+    /// Both players ran out of moves and the player scored more.
+    Outscored(usize),
+    /// Both players ran out of moves with the same score.
+    Tie,
+}
+
+impl Outcome {
+    pub fn winner(self) -> Option<usize> {
+        match self {
+            Outcome::Reached(player) | Outcome::Outscored(player) => Some(player),
+            Outcome::Tie => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Race {
+    games: [Game; PLAYERS],
+    outcome: Option<Outcome>,
+}
+
+impl Race {
+    /// Starts both players on the same board with the same spawns to come.
+    pub fn new(seed: u64) -> Race {
+        Race::from_games([Game::with_seed(seed), Game::with_seed(seed)])
+    }
+
+    /// Races from the given games, which need not be alike. Useful for setting up positions.
+    pub fn from_games(games: [Game; PLAYERS]) -> Race {
+        let mut race = Race { games, outcome: None };
+        race.update_outcome();
+        race
+    }
+
+    pub fn game(&self, player: usize) -> &Game {
+        &self.games[player]
+    }
+
+    /// How the race was decided, or `None` while it is still on.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Moves on `player`'s board, returning the ID of the tile spawned after the move. No moves are
+    /// accepted once the race has been decided.
+    pub fn make_move(&mut self, player: usize, direction: Direction) -> Result<Option<usize>, InvalidMove> {
+        if self.outcome.is_some() {
+            return Err(InvalidMove)
+        }
+
+        let new_tile_id = match self.games[player].make_move(direction) {
+            InputResult::Ok(new_tile_id, _, _) => new_tile_id,
+            InputResult::Err(invalid_move) => return Err(invalid_move),
+        };
+
+        self.update_outcome();
+        Ok(new_tile_id)
+    }
+
+    fn update_outcome(&mut self) {
+        if self.outcome.is_some() {
+            return
+        }
+
+        if let Some(player) = (0..PLAYERS).find(|&player| self.games[player].max_tile() >= self.games[player].config().goal_tile) {
+            self.outcome = Some(Outcome::Reached(player));
+        } else if self.games.iter().all(|game| game.game_over()) {
+            self.outcome = Some(match self.games[0].score.cmp(&self.games[1].score) {
+                Ordering::Greater => Outcome::Outscored(0),
+                Ordering::Less => Outcome::Outscored(1),
+                Ordering::Equal => Outcome::Tie,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn::{ScriptedSpawner, Spawn};
+
+    #[test]
+    fn test_controls() {
+        assert_eq!(Controls::Wasd.direction("KeyA"), Some(Direction::Left));
+        assert_eq!(Controls::Wasd.direction("ArrowLeft"), None);
+        assert_eq!(Controls::Arrows.direction("ArrowUp"), Some(Direction::Up));
+        assert_eq!(Controls::Arrows.direction("KeyK"), None);
+
+        // Both players start from the same board and see the same spawns.
+        let mut race = Race::new(9);
+        assert!(race.game(0) == race.game(1));
+
+        let direction = Direction::ALL.into_iter().find(|&direction| race.game(0).afterstate(direction).is_some()).unwrap();
+        assert!(race.make_move(0, direction).is_ok());
+        assert!(race.make_move(1, direction).is_ok());
+        assert!(race.game(0) == race.game(1));
+    }
+
+    #[test]
+    fn test_first_to_goal_wins() {
+        let mut race = Race::from_games([
+            Game::from_notation("2,4,0,0/0,0,0,0/0,0,0,0/0,0,0,0").unwrap(),
+            Game::from_notation("1024,1024,0,0/0,0,0,0/0,0,0,0/0,0,0,0").unwrap(),
+        ]);
+
+        assert!(race.make_move(1, Direction::Up).is_err());
+        assert_eq!(race.outcome(), None);
+        assert!(race.make_move(1, Direction::Left).is_ok());
+        assert_eq!(race.outcome(), Some(Outcome::Reached(1)));
+        assert!(race.make_move(0, Direction::Right).is_err());
+    }
+
+    #[test]
+    fn test_higher_score_wins_when_both_are_stuck() {
+        let stuck = "2,4,2,4/4,2,4,2/2,4,2,4/4,2,4,2";
+        let race = Race::from_games([
+            Game::from_notation(&format!("{} score=100", stuck)).unwrap(),
+            Game::from_notation(&format!("{} score=60", stuck)).unwrap(),
+        ]);
+        assert_eq!(race.outcome().and_then(Outcome::winner), Some(0));
+
+        // A player who is stuck waits for the other to finish.
+        let mut race = Race::from_games([
+            Game::from_notation(&format!("{} score=100", stuck)).unwrap(),
+            Game::from_notation("2,4,2,4/4,2,4,2/2,4,2,4/8,16,4,4 score=92").unwrap(),
+        ]);
+        assert_eq!(race.outcome(), None);
+
+        // Sliding right scores 8 and a 4 in the corner leaves no move.
+        race.games[1].set_spawner(Box::new(ScriptedSpawner::new([Spawn { row: 3, col: 0, value: 4 }])));
+        assert!(race.make_move(1, Direction::Right).is_ok());
+        assert_eq!(race.game(1).score, 100);
+        assert_eq!(race.outcome(), Some(Outcome::Tie));
+    }
+}
@@ -0,0 +1,187 @@
+use rust_2048::challenge;
+use rust_2048::versus::{Controls, Outcome, Race, PLAYERS};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlElement;
+use yew::prelude::*;
+
+use crate::*;
+
+/// Animates the moves of one player on their own board. Both players move at the same time, so
+/// each board has its own loop and its own animationend channel.
+async fn process_player_moves(player: usize, race: Rc<RefCell<Race>>, mut move_rx: UnboundedReceiver<Direction>, mut animationend_rx: counted_channel::CountedReceiver) {
+    while let Some(direction) = move_rx.recv().await {
+        let Ok(new_tile_id) = race.borrow_mut().make_move(player, direction) else {
+            continue
+        };
+
+        // The race is not borrowed while animating, so that the other player can keep moving.
+        let game = race.borrow().game(player).clone();
+        animate_move(&player_board(player), &game.get_tiles(), new_tile_id, &mut animationend_rx).await;
+        update_player_score(player, game.score);
+
+        if game.game_over() {
+            set_player_status(player, "No moves left");
+        }
+
+        if let Some(outcome) = race.borrow().outcome() {
+            show_results(outcome, &race.borrow());
+        }
+    }
+}
+
+/// Deals a new race from a random seed and draws both boards.
+fn start_race(race: &RefCell<Race>) {
+    let seed = challenge::random_seed();
+    *race.borrow_mut() = Race::new(seed);
+
+    for player in 0..PLAYERS {
+        render_board(&player_board(player), race.borrow().game(player));
+        update_player_score(player, 0);
+        set_player_status(player, "");
+    }
+
+    let document = gloo::utils::document();
+    let seed_text = document.query_selector(".versus-seed").unwrap().unwrap();
+    seed_text.set_text_content(Some(&format!("Seed {}", challenge::encode(seed))));
+
+    let results = document.query_selector(".versus-results").unwrap().unwrap();
+    results.dyn_ref::<HtmlElement>().unwrap().set_hidden(true);
+}
+
+#[derive(Properties, PartialEq)]
+pub struct VersusProps {
+    pub close_callback: Callback<MouseEvent>,
+}
+
+/// Two players racing side by side on boards dealt from the same seed, one steering with WASD and
+/// the other with the arrow keys. See `rust_2048::versus` for how a race is decided.
+#[function_component(Versus)]
+pub fn versus(props: &VersusProps) -> Html {
+    let race = Rc::new(RefCell::new(Race::new(0)));
+    let mut move_txs: Vec<UnboundedSender<Direction>> = Vec::new();
+    let mut animationend_txs = Vec::new();
+
+    for player in 0..PLAYERS {
+        let (move_tx, move_rx) = mpsc::unbounded_channel();
+        let (animationend_tx, animationend_rx) = counted_channel::CountedChannel::new();
+
+        spawn_local(process_player_moves(player, Rc::clone(&race), move_rx, animationend_rx));
+        move_txs.push(move_tx);
+        animationend_txs.push(animationend_tx);
+    }
+
+    {
+        let race = Rc::clone(&race);
+
+        use_effect(move || {
+            start_race(&race);
+
+            // Every key goes to the player whose controls it belongs to. The loops stop once this
+            // closure, and with it their senders, is dropped.
+            let keydown_callback = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+                for (controls, move_tx) in Controls::ALL.iter().zip(&move_txs) {
+                    if let Some(direction) = controls.direction(&event.code()) {
+                        move_tx.send(direction).unwrap();
+                    }
+                }
+            }) as Box<dyn FnMut(KeyboardEvent)>);
+
+            // Animation events are listened to on each board, since they bubble up from its tiles.
+            let animationend_callbacks: Vec<_> = animationend_txs.into_iter().map(animationend_callback).collect();
+
+            let document = gloo::utils::document();
+            document.add_event_listener_with_callback("keydown", keydown_callback.as_ref().unchecked_ref()).unwrap();
+
+            for (player, callback) in animationend_callbacks.iter().enumerate() {
+                let board = player_board(player);
+                board.add_event_listener_with_callback("animationend", callback.as_ref().unchecked_ref()).unwrap();
+                board.add_event_listener_with_callback("animationcancel", callback.as_ref().unchecked_ref()).unwrap();
+            }
+
+            move || {
+                let document = gloo::utils::document();
+                document.remove_event_listener_with_callback("keydown", keydown_callback.as_ref().unchecked_ref()).unwrap();
+                drop(keydown_callback);
+                drop(animationend_callbacks);
+            }
+        });
+    }
+
+    let rematch_callback = Callback::from(move |_| start_race(&race));
+
+    let style_args = button_style_args();
+    let label_style = format!("--status_text: {}", COLORS.text_light);
+    let results_style = format!("--results_background: {}{}; --results_text: {};", COLORS.text_light, COLORS.opacity, COLORS.text_dark);
+
+    html! {
+        <div class="content versus noselect">
+            <div class="metadata-container">
+                <div class="metadata versus-seed" style={style_args.clone()}/>
+                <NewGameButton onclick={props.close_callback.clone()} button_text={"Back to Game"} disabled={false}/>
+            </div>
+            <div class="versus-boards">
+                { for Controls::ALL.iter().enumerate().map(|(player, controls)| html! {
+                    <div class="versus-player">
+                        <div class="versus-header" style={label_style.clone()}>
+                            <span>{ controls.name() }</span>
+                            <span class={format!("versus-status player-{}", player)}/>
+                            <div class={format!("metadata versus-score player-{}", player)} style={style_args.clone()}>{ 0 }</div>
+                        </div>
+                        <div class={format!("board-container player-{}", player)}>
+                            <GameBoard/>
+                        </div>
+                    </div>
+                })}
+                <div hidden=true class="versus-results" style={results_style}>
+                    <div class="text"/>
+                    <div class="buttons">
+                        <NewGameButton onclick={rematch_callback} button_text={"Rematch"} disabled={false}/>
+                        <NewGameButton onclick={props.close_callback.clone()} button_text={"Back to Game"} disabled={false}/>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
+
+// Helper functions
+
+fn player_board(player: usize) -> Element {
+    let document = gloo::utils::document();
+    document.query_selector(&format!(".board-container.player-{}", player)).unwrap().unwrap()
+}
+
+fn update_player_score(player: usize, score: u32) {
+    let document = gloo::utils::document();
+    let score_node = document.query_selector(&format!(".versus-score.player-{}", player)).unwrap().unwrap();
+    score_node.set_inner_html(&score.to_string());
+}
+
+fn set_player_status(player: usize, text: &str) {
+    let document = gloo::utils::document();
+    let status = document.query_selector(&format!(".versus-status.player-{}", player)).unwrap().unwrap();
+    status.set_text_content(Some(text));
+}
+
+fn show_results(outcome: Outcome, race: &Race) {
+    let name = |player: usize| Controls::ALL[player].name();
+
+    let text = match outcome {
+        Outcome::Reached(player) => {
+            format!("{} wins by reaching {}!", name(player), race.game(player).config().goal_tile)
+        },
+        Outcome::Outscored(player) => {
+            format!("{} wins {} to {}!", name(player), race.game(player).score, race.game(1 - player).score)
+        },
+        Outcome::Tie => format!("Tie at {}!", race.game(0).score),
+    };
+
+    let document = gloo::utils::document();
+    let results = document.query_selector(".versus-results").unwrap().unwrap();
+    results.query_selector(".text").unwrap().unwrap().set_text_content(Some(&text));
+    results.dyn_ref::<HtmlElement>().unwrap().set_hidden(false);
+}
@@ -0,0 +1,33 @@
+//! Plays whole races against a local race server. Run with `cargo test --features server`.
+#![cfg(feature = "server")]
+
+use rust_2048::race_server::{self, ServerConfig};
+use rust_2048::strategy;
+use std::net::TcpListener;
+use std::thread;
+
+#[test]
+fn test_bots_play_a_full_race() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || race_server::serve(listener, ServerConfig { players: 2, seed: Some(7) }));
+
+    let bots: Vec<_> = ["random", "greedy"].into_iter()
+        .map(|name| {
+            let address = address.clone();
+
+            thread::spawn(move || {
+                let mut strategy = strategy::by_name(name).unwrap();
+                race_server::play(&address, name, strategy.as_mut()).unwrap()
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = bots.into_iter().map(|bot| bot.join().unwrap()).collect();
+
+    // Both players are told the same result, in which nobody can move any more.
+    assert_eq!(results[0].standings, results[1].standings);
+    assert_eq!(results[0].winner, results[1].winner);
+    assert!(results[0].standings.iter().all(|standing| standing.out && standing.moves > 0));
+    assert_ne!(results[0].player, results[1].player);
+}
@@ -0,0 +1,287 @@
+//! Runs every engine scenario in `tests/scenarios`.
+//!
+//! A scenario is a plain text file of commands run top to bottom, one per line, with `#` starting a
+//! comment:
+//!
+//! ```text
+//! start 2,2,0,0/0,0,0,0/0,0,0,0/0,0,0,0    # starting position, in `Game::from_notation()` form
+//! seed 7                                   # seeds spawns that are not forced, 0 by default
+//! topology hex                             # `square` by default; hex boards add upright and downleft
+//! edges wrapped                            # `bounded` by default; wrapped lines are rings
+//! move left spawn 2 at 3,3                 # slides, then spawns a 2 at row 3, column 3
+//! move up                                  # slides and spawns a tile drawn from the seed
+//! move right no spawn                      # slides without spawning
+//! reject down                              # the move must not change the board
+//! expect 4,0,0,0/0,0,0,0/0,0,0,0/0,0,0,2   # board, in the same form as `start`
+//! score 4
+//! state playing                            # `playing`, `won` or `lost`
+//! ```
+//!
+//! Every failing check in every scenario is reported with its file and line, and board mismatches
+//! are shown side by side with the differing tiles marked.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rust_2048::notation::Position;
+use rust_2048::spawn::{NoSpawner, ScriptedSpawner, Spawn};
+use rust_2048::{Direction, Edges, ExponentGrid, Game, InputResult, Topology, BOARD_DIMENSION};
+
+/// How a move is followed up.
+#[derive(Clone, Copy)]
+enum MoveSpawn {
+    /// A tile drawn from the game's generator, as in a real game.
+    Random,
+    /// A tile of `value` at `row`, `col`.
+    Forced { value: u32, row: usize, col: usize },
+    None,
+}
+
+#[test]
+fn test_scenarios() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("scenarios");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&directory)
+        .expect("Scenario directory should exist.")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "scenario"))
+        .collect();
+    paths.sort();
+
+    assert!(!paths.is_empty(), "No scenarios found in {}", directory.display());
+
+    let failures: Vec<String> = paths.iter().flat_map(|path| run_scenario(path)).collect();
+
+    if !failures.is_empty() {
+        panic!("{} of {} scenarios failed checks:\n\n{}", failing_files(&failures), paths.len(), failures.join("\n\n"));
+    }
+}
+
+/// Runs one scenario and returns a description of every check that failed.
+fn run_scenario(path: &Path) -> Vec<String> {
+    let name = path.file_name().unwrap().to_string_lossy().into_owned();
+    let text = fs::read_to_string(path).expect("Scenario should be readable.");
+    let mut failures = Vec::new();
+    let mut game: Option<Game> = None;
+    let mut seed = 0;
+    let mut topology = Topology::Square;
+    let mut edges = Edges::Bounded;
+
+    for (index, line) in text.lines().enumerate() {
+        let location = format!("{}:{}", name, index + 1);
+        let line = line.split('#').next().unwrap().trim();
+
+        if line.is_empty() {
+            continue
+        }
+
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let argument = argument.trim();
+
+        if command == "start" {
+            match argument.parse::<Position>() {
+                Ok(position) => {
+                    let mut started = position.to_game(Some(seed));
+                    started.set_topology(topology);
+                    started.set_edges(edges);
+                    game = Some(started);
+                },
+                Err(error) => return vec![format!("{}: invalid start position: {}", location, error)],
+            }
+            continue
+        }
+
+        if command == "seed" {
+            match argument.parse() {
+                Ok(value) => seed = value,
+                Err(_) => return vec![format!("{}: invalid seed {:?}", location, argument)],
+            }
+
+            if let Some(game) = game.as_mut() {
+                game.reseed(seed);
+            }
+            continue
+        }
+
+        if command == "topology" {
+            match Topology::ALL.into_iter().find(|topology| topology.name().eq_ignore_ascii_case(argument)) {
+                Some(value) => topology = value,
+                None => return vec![format!("{}: invalid topology {:?}", location, argument)],
+            }
+
+            if let Some(game) = game.as_mut() {
+                game.set_topology(topology);
+            }
+            continue
+        }
+
+        if command == "edges" {
+            match Edges::ALL.into_iter().find(|edges| edges.name().eq_ignore_ascii_case(argument)) {
+                Some(value) => edges = value,
+                None => return vec![format!("{}: invalid edges {:?}", location, argument)],
+            }
+
+            if let Some(game) = game.as_mut() {
+                game.set_edges(edges);
+            }
+            continue
+        }
+
+        let Some(game) = game.as_mut() else {
+            return vec![format!("{}: `{}` before `start`", location, command)]
+        };
+
+        let result = match command {
+            "move" => parse_move(argument).and_then(|(direction, spawn)| make_move(game, direction, spawn)),
+            "reject" => parse_direction(argument).and_then(|direction| {
+                match game.afterstate(direction) {
+                    Some(_) => Err(format!("expected {:?} to be rejected, but it moves tiles", direction)),
+                    None => Ok(()),
+                }
+            }),
+            "expect" => argument.parse::<Position>()
+                .map_err(|error| format!("invalid expected board: {}", error))
+                .and_then(|expected| compare_boards(&expected.exponents, &game.exponents())),
+            "score" => argument.parse::<u32>()
+                .map_err(|_| format!("invalid score {:?}", argument))
+                .and_then(|expected| match game.score == expected {
+                    true => Ok(()),
+                    false => Err(format!("expected score {}, found {}", expected, game.score)),
+                }),
+            "state" => {
+                let actual = state(game);
+                match ["playing", "won", "lost"].contains(&argument) {
+                    false => Err(format!("invalid state {:?}", argument)),
+                    true if actual == argument => Ok(()),
+                    true => Err(format!("expected state {}, found {}", argument, actual)),
+                }
+            },
+            _ => Err(format!("unknown command {:?}", command)),
+        };
+
+        if let Err(message) = result {
+            failures.push(format!("{}: {}", location, message));
+        }
+    }
+
+    failures
+}
+
+/// Parses `<direction> [spawn <value> at <row>,<col> | no spawn]`.
+fn parse_move(argument: &str) -> Result<(Direction, MoveSpawn), String> {
+    let words: Vec<&str> = argument.split_whitespace().collect();
+    let direction = parse_direction(words.first().copied().unwrap_or(""))?;
+
+    let spawn = match words[1..] {
+        [] => MoveSpawn::Random,
+        ["no", "spawn"] => MoveSpawn::None,
+        ["spawn", value, "at", slot] => {
+            let value = value.parse().map_err(|_| format!("invalid spawn value {:?}", value))?;
+            let (row, col) = slot.split_once(',')
+                .and_then(|(row, col)| Some((row.parse().ok()?, col.parse().ok()?)))
+                .ok_or_else(|| format!("invalid spawn slot {:?}, expected <row>,<col>", slot))?;
+
+            MoveSpawn::Forced { value, row, col }
+        },
+        _ => return Err(format!("invalid move {:?}", argument)),
+    };
+
+    Ok((direction, spawn))
+}
+
+fn parse_direction(word: &str) -> Result<Direction, String> {
+    match word {
+        "up" => Ok(Direction::Up),
+        "down" => Ok(Direction::Down),
+        "left" => Ok(Direction::Left),
+        "right" => Ok(Direction::Right),
+        "upright" => Ok(Direction::UpRight),
+        "downleft" => Ok(Direction::DownLeft),
+        _ => Err(format!("invalid direction {:?}", word)),
+    }
+}
+
+fn make_move(game: &mut Game, direction: Direction, spawn: MoveSpawn) -> Result<(), String> {
+    let seeded = game.spawner().clone_box();
+
+    match spawn {
+        MoveSpawn::Random => (),
+        MoveSpawn::Forced { value, row, col } => {
+            game.set_spawner(Box::new(ScriptedSpawner::new([Spawn { row, col, value }])));
+        },
+        MoveSpawn::None => game.set_spawner(Box::new(NoSpawner)),
+    }
+
+    let result = match game.make_move(direction) {
+        InputResult::Ok(new_id, _, _) => match (spawn, new_id) {
+            (MoveSpawn::Forced { value, row, col }, None) => {
+                Err(format!("cannot spawn a {} at {},{}, the slot is occupied or off the board\n{}",
+                            value, row, col, render_board(&game.exponents(), None)))
+            },
+            _ => Ok(()),
+        },
+        InputResult::Err(_) => Err(format!("{:?} did not move any tiles", direction)),
+    };
+
+    // Forced spawns do not use up the seeded spawns that follow.
+    if !matches!(spawn, MoveSpawn::Random) {
+        game.set_spawner(seeded);
+    }
+
+    result
+}
+
+/// A game that has reached the goal tile is won even if no moves are left.
+fn state(game: &Game) -> &'static str {
+    if game.max_tile() >= game.config().goal_tile {
+        "won"
+    } else if game.game_over() {
+        "lost"
+    } else {
+        "playing"
+    }
+}
+
+fn compare_boards(expected: &ExponentGrid, actual: &ExponentGrid) -> Result<(), String> {
+    if expected == actual {
+        return Ok(())
+    }
+
+    let expected_lines = render_board(expected, Some(actual));
+    let actual_lines = render_board(actual, Some(expected));
+    let mut message = String::from("board differs (mismatched tiles marked with *)\n");
+    writeln!(message, "  {:<32}actual", "expected").unwrap();
+
+    for (expected_line, actual_line) in expected_lines.lines().zip(actual_lines.lines()) {
+        writeln!(message, "  {:<32}{}", expected_line, actual_line).unwrap();
+    }
+
+    Err(message.trim_end().to_string())
+}
+
+/// Renders a board one row per line, marking the tiles that differ from `other`.
+fn render_board(board: &ExponentGrid, other: Option<&ExponentGrid>) -> String {
+    let mut rendered = String::new();
+
+    for row in 0..BOARD_DIMENSION {
+        for col in 0..BOARD_DIMENSION {
+            let value = if board[row][col] == 0 { 0 } else { 1u32 << board[row][col] };
+            let marker = match other {
+                Some(other) if other[row][col] != board[row][col] => "*",
+                _ => " ",
+            };
+
+            write!(rendered, "{:>6}{}", value, marker).unwrap();
+        }
+
+        rendered.push('\n');
+    }
+
+    rendered
+}
+
+fn failing_files(failures: &[String]) -> usize {
+    let mut files: Vec<&str> = failures.iter().map(|failure| failure.split(':').next().unwrap()).collect();
+    files.dedup();
+    files.len()
+}